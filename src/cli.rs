@@ -1,11 +1,138 @@
+use crate::presets::preset;
 use clap::{App, ArgMatches};
+use encoding_rs::{Encoding, UTF_8};
 use regex::Regex;
 use std::default::Default;
+use std::str::FromStr;
 
+/// The default format of a type, used when a type expression has no format specifier. The format can be
+/// restricted to a field, e.g. the `time_local` field of a web server log.
 #[derive(Debug, Default)]
 pub struct DataDef {
-    type_name: String,
-    format: String,
+    pub type_name: String,
+    pub format: String,
+    /// the type_term_arg the format applies to as written, e.g. `time_local`, or None for all fields
+    pub field: Option<String>,
+}
+
+impl DataDef {
+    pub fn for_field(type_name: &str, format: &str, field: &str) -> DataDef {
+        DataDef {
+            type_name: String::from(type_name),
+            format: String::from(format),
+            field: Some(String::from(field)),
+        }
+    }
+
+    /// Returns the default format of the type for the type_term_arg, a format for the field takes precedence
+    /// over a format for all fields.
+    pub fn find_format<'d>(
+        data_defs: &'d [DataDef],
+        type_name: &str,
+        type_term_arg: &str,
+    ) -> Option<&'d str> {
+        let of_type = || data_defs.iter().filter(|def| def.type_name == type_name);
+
+        of_type()
+            .find(|def| def.field.as_deref() == Some(type_term_arg))
+            .or_else(|| of_type().find(|def| def.field.is_none()))
+            .map(|def| def.format.as_str())
+    }
+}
+
+/// What to do with a line that fails to evaluate, e.g. because a value in the expression
+/// cannot be compared with the token found in the line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    /// Silently skip the line
+    Skip,
+    /// Report the line on stderr and skip it
+    Warn,
+    /// Abort processing
+    Fail,
+}
+
+impl FromStr for ErrorPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(ErrorPolicy::Skip),
+            "warn" => Ok(ErrorPolicy::Warn),
+            "fail" => Ok(ErrorPolicy::Fail),
+            _ => Err(format!("Unknown error policy {}", s)),
+        }
+    }
+}
+
+/// How a matching line is written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The line as read, or the fields selected by --print
+    Text,
+    /// A JSON object per line (JSON Lines)
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("Unknown output format {}", s)),
+        }
+    }
+}
+
+/// How a line is split into tokens.
+#[derive(Debug, Clone)]
+pub enum InputFormat {
+    /// Tokens are separated by the token separator regex
+    Split,
+    /// Tokens are the fields of a CSV record, quoted as described in RFC 4180
+    Csv { delimiter: u8 },
+    /// Tokens are the capture groups of a regex, named groups name the tokens
+    Pattern(Regex),
+    /// Each line is a JSON value (JSON Lines), values are addressed by path, e.g. `.request.user.id`
+    Json,
+    /// Tokens are the values of `key=value` pairs named by their keys, values may be quoted (logfmt)
+    Logfmt,
+}
+
+impl InputFormat {
+    /// Returns the field names known before any line is read.
+    pub fn field_names(&self) -> Vec<&str> {
+        match self {
+            InputFormat::Pattern(pattern) => pattern.capture_names().flatten().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// What to do with a line that doesn't have the input format, e.g. doesn't match the pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unmatched {
+    /// The line is skipped
+    Skip,
+    /// The line is written to the output without being evaluated
+    Pass,
+    /// The line is split into tokens by the token separator and evaluated
+    Split,
+}
+
+impl FromStr for Unmatched {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Unmatched::Skip),
+            "pass" => Ok(Unmatched::Pass),
+            "split" => Ok(Unmatched::Split),
+            _ => Err(format!("Unknown unmatched line handling {}", s)),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -13,44 +140,150 @@ pub struct CommandArgs {
     pub expr: String,
     pub data_def: Vec<DataDef>,
     pub token_regex: Regex,
+    pub quiet: bool,
+    pub on_error: ErrorPolicy,
+    pub encoding: &'static Encoding,
+    pub print: Option<String>,
+    pub output_sep: String,
+    pub output: OutputFormat,
+    pub files: Vec<String>,
+    pub input: InputFormat,
+    pub header: bool,
+    pub unmatched: Unmatched,
 }
 
-pub fn parse_cli() -> CommandArgs {
+/// Parses the command line, usage errors are returned to the caller so that they can be reported with the
+/// proper exit status.
+pub fn parse_cli() -> Result<CommandArgs, clap::Error> {
     let yaml = load_yaml!("cli.yaml");
-    let matches = App::from_yaml(yaml).get_matches();
+    let matches = App::from_yaml(yaml).get_matches_safe()?;
+    let (input, preset_data_defs) = get_input_format(&matches)?;
 
-    CommandArgs {
+    Ok(CommandArgs {
         expr: matches.value_of("expr").unwrap().to_string(),
-        data_def: get_data_def(&matches),
-        token_regex: get_token_sep(&matches),
-    }
+        data_def: merge_data_defs(get_data_def(&matches)?, preset_data_defs),
+        token_regex: get_token_sep(&matches)?,
+        quiet: matches.is_present("quiet"),
+        on_error: get_error_policy(&matches),
+        encoding: get_encoding(&matches)?,
+        print: matches.value_of("print").map(String::from),
+        output_sep: String::from(matches.value_of("output-sep").unwrap_or(" ")),
+        // the possible values are restricted in cli.yaml so parsing can't fail here
+        output: value_t!(matches, "output", OutputFormat).unwrap_or(OutputFormat::Text),
+        files: matches
+            .values_of("files")
+            .map(|files| files.map(String::from).collect())
+            .unwrap_or_default(),
+        unmatched: get_unmatched(&matches, &input)?,
+        input,
+        header: matches.is_present("header"),
+    })
 }
 
-fn get_data_def(matches: &ArgMatches) -> Vec<DataDef> {
-    if let Some(data_defs) = matches.value_of("data-def") {
-        // e.g. date|yyyy/mm/dd, string|regexp, ...
+fn get_data_def(matches: &ArgMatches) -> Result<Vec<DataDef>, clap::Error> {
+    if let Some(data_defs) = matches.values_of("data-def") {
+        // e.g. date|%Y/%m/%d, timestamp|%d/%b/%Y:%H:%M:%S %z, ...
         data_defs
-            .split(',')
-            .into_iter()
-            .map(|def| {
-                let type_def: Vec<&str> = def.split('|').collect();
-                DataDef {
-                    type_name: String::from(type_def[0]),
-                    format: String::from(type_def[1]),
-                }
+            .flat_map(|data_defs| data_defs.split(','))
+            .map(|def| match def.split_once('|') {
+                Some((type_name, format)) => Ok(DataDef {
+                    type_name: String::from(type_name.trim()),
+                    format: String::from(format),
+                    field: None,
+                }),
+                None => Err(clap::Error::value_validation_auto(format!(
+                    "Invalid data definition '{}', expected <typeName>|<formatString>",
+                    def
+                ))),
             })
-            .collect::<Vec<DataDef>>()
+            .collect()
+    } else {
+        Ok(Default::default())
+    }
+}
+
+/// Returns the formats given on the command line and the formats of a preset. A format given on the command
+/// line overrides the preset formats of the type, also those for a single field.
+fn merge_data_defs(data_defs: Vec<DataDef>, preset_data_defs: Vec<DataDef>) -> Vec<DataDef> {
+    let preset_data_defs: Vec<DataDef> = preset_data_defs
+        .into_iter()
+        .filter(|preset_def| {
+            !data_defs
+                .iter()
+                .any(|def| def.type_name == preset_def.type_name)
+        })
+        .collect();
+
+    data_defs.into_iter().chain(preset_data_defs).collect()
+}
+
+fn get_error_policy(matches: &ArgMatches) -> ErrorPolicy {
+    // the possible values are restricted in cli.yaml so parsing can't fail here
+    value_t!(matches, "on-error", ErrorPolicy).unwrap_or(ErrorPolicy::Warn)
+}
+
+fn get_encoding(matches: &ArgMatches) -> Result<&'static Encoding, clap::Error> {
+    // e.g. utf-8, latin1, utf-16le
+    match matches.value_of("encoding") {
+        Some(label) => Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+            clap::Error::value_validation_auto(format!("Unknown encoding '{}'", label))
+        }),
+        None => Ok(UTF_8),
+    }
+}
+
+/// Returns the input format and, for a preset log format, the default formats of its fields.
+fn get_input_format(matches: &ArgMatches) -> Result<(InputFormat, Vec<DataDef>), clap::Error> {
+    if let Some(name) = matches.value_of("format") {
+        return preset(name).map_err(clap::Error::value_validation_auto);
+    }
+
+    let input = if matches.is_present("csv") {
+        Ok(InputFormat::Csv { delimiter: b',' })
+    } else if matches.is_present("tsv") {
+        Ok(InputFormat::Csv { delimiter: b'\t' })
+    } else if matches.is_present("json") {
+        Ok(InputFormat::Json)
+    } else if matches.is_present("logfmt") {
+        Ok(InputFormat::Logfmt)
+    } else if let Some(pattern) = matches.value_of("pattern") {
+        // e.g. (?P<ts>\S+ \S+) \[(?P<lvl>\w+)\] (?P<msg>.*)
+        Regex::new(pattern).map(InputFormat::Pattern).map_err(|e| {
+            clap::Error::value_validation_auto(format!("Invalid pattern '{}': {}", pattern, e))
+        })
     } else {
-        Default::default()
+        Ok(InputFormat::Split)
+    }?;
+
+    Ok((input, Vec::new()))
+}
+
+/// Returns how lines that don't have the input format are handled, only a pattern or JSON input, including
+/// the presets, can have such lines.
+fn get_unmatched(matches: &ArgMatches, input: &InputFormat) -> Result<Unmatched, clap::Error> {
+    match input {
+        // the possible values are restricted in cli.yaml so parsing can't fail here
+        InputFormat::Pattern(_) | InputFormat::Json => {
+            Ok(value_t!(matches, "unmatched", Unmatched).unwrap_or(Unmatched::Skip))
+        }
+        _ if matches.is_present("unmatched") => Err(clap::Error::value_validation_auto(
+            String::from("--unmatched requires --pattern, --json or --format"),
+        )),
+        _ => Ok(Unmatched::Skip),
     }
 }
 
-fn get_token_sep(matches: &ArgMatches) -> Regex {
+fn get_token_sep(matches: &ArgMatches) -> Result<Regex, clap::Error> {
     if let Some(token_separators) = matches.value_of("token-sep") {
         // e.g. "," or "<>" or " "
-        Regex::new(token_separators).unwrap()
+        Regex::new(token_separators).map_err(|e| {
+            clap::Error::value_validation_auto(format!(
+                "Invalid token separator '{}': {}",
+                token_separators, e
+            ))
+        })
     } else {
-        Regex::new(" ").unwrap()
+        Ok(Regex::new(" ").unwrap())
     }
 }
 
@@ -67,23 +300,242 @@ mod tests {
             r#"--token-sep=",""#,
             "--data-def=date|yyyy/MM/dd",
         ];
-        let _target_vec = vec![DataDef {
+        let _target_vec = [DataDef {
             type_name: String::from("date"),
             format: String::from("yyyy/MM/dd"),
+            field: None,
         }];
 
         let yaml = load_yaml!("cli.yaml");
         let matches = App::from_yaml(yaml).get_matches_from(arg_vec);
-        let data_defs = get_data_def(&matches);
-        let token_sep = get_token_sep(&matches);
+        let data_defs = get_data_def(&matches).unwrap();
+        let token_sep = get_token_sep(&matches).unwrap();
 
         assert!(matches!(data_defs, _target_vec));
         assert!(token_sep.as_str() == r#"",""#);
 
+        let matches =
+            App::from_yaml(yaml).get_matches_from(vec!["semfilter", "string(0) == x", "-t", "("]);
+        assert!(get_token_sep(&matches).is_err());
+
         //println!("data_defs: {:?}", data_defs);
         //println!("token_sep: {:?}", token_sep);
     }
 
+    #[test]
+    fn test_error_policy() {
+        let yaml = load_yaml!("cli.yaml");
+
+        let matches =
+            App::from_yaml(yaml).get_matches_from(vec!["semfilter", "date(0) == 1900-01-01"]);
+        assert_eq!(get_error_policy(&matches), ErrorPolicy::Warn);
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "date(0) == 1900-01-01",
+            "--on-error=skip",
+        ]);
+        assert_eq!(get_error_policy(&matches), ErrorPolicy::Skip);
+
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec![
+                "semfilter",
+                "date(0) == 1900-01-01",
+                "--on-error=ignore"
+            ])
+            .is_err());
+    }
+
+    #[test]
+    fn test_encoding() {
+        let yaml = load_yaml!("cli.yaml");
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec!["semfilter", "string(0) == x"]);
+        assert_eq!(get_encoding(&matches).unwrap(), UTF_8);
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "string(0) == x",
+            "--encoding=latin1",
+        ]);
+        assert_eq!(get_encoding(&matches).unwrap().name(), "windows-1252");
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "string(0) == x",
+            "--encoding=klingon",
+        ]);
+        assert!(get_encoding(&matches).is_err());
+    }
+
+    #[test]
+    fn test_output_and_files() {
+        let yaml = load_yaml!("cli.yaml");
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "string(0) == x",
+            "--output=json",
+            "a.log",
+            "b.log",
+        ]);
+        assert_eq!(
+            value_t!(matches, "output", OutputFormat).unwrap(),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            matches.values_of("files").unwrap().collect::<Vec<_>>(),
+            vec!["a.log", "b.log"]
+        );
+
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec![
+                "semfilter",
+                "string(0) == x",
+                "--output=json",
+                "--print=string(0)"
+            ])
+            .is_err());
+    }
+
+    #[test]
+    fn test_input_format() {
+        let yaml = load_yaml!("cli.yaml");
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec!["semfilter", "string(0) == x"]);
+        assert!(matches!(
+            get_input_format(&matches).unwrap().0,
+            InputFormat::Split
+        ));
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "integer(status) > 499",
+            "--tsv",
+            "--header",
+        ]);
+        assert!(matches!(
+            get_input_format(&matches).unwrap().0,
+            InputFormat::Csv { delimiter: b'\t' }
+        ));
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "loglevel(lvl) >= WARN",
+            r"--pattern=(?P<ts>\S+ \S+) \[(?P<lvl>\w+)\] (?P<msg>.*)",
+        ]);
+        let (input_format, _) = get_input_format(&matches).unwrap();
+        assert_eq!(input_format.field_names(), vec!["ts", "lvl", "msg"]);
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "loglevel(lvl) >= WARN",
+            "--pattern=(?P<lvl>",
+        ]);
+        assert!(get_input_format(&matches).is_err());
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "integer(.status) >= 500",
+            "--json",
+            "--unmatched=split",
+        ]);
+        assert!(matches!(
+            get_input_format(&matches).unwrap().0,
+            InputFormat::Json
+        ));
+        assert_eq!(
+            get_unmatched(&matches, &InputFormat::Json).unwrap(),
+            Unmatched::Split
+        );
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "string(0) == x",
+            "--csv",
+            "--unmatched=pass",
+        ]);
+        let (input_format, _) = get_input_format(&matches).unwrap();
+        assert!(get_unmatched(&matches, &input_format).is_err());
+
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec!["semfilter", "string(0) == x", "--json", "--csv"])
+            .is_err());
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "loglevel(level) >= WARN",
+            "--logfmt",
+        ]);
+        assert!(matches!(
+            get_input_format(&matches).unwrap().0,
+            InputFormat::Logfmt
+        ));
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec!["semfilter", "string(0) == x", "--logfmt", "--json"])
+            .is_err());
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec!["semfilter", "string(0) == x", "--header"])
+            .is_err());
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec!["semfilter", "string(0) == x", "--csv", "--tsv"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_data_def_and_format() {
+        let yaml = load_yaml!("cli.yaml");
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "timestamp(time_local) > 2026-01-01",
+            "--format=nginx",
+            "-d",
+            "date|%Y/%m/%d,timestamp|%s",
+        ]);
+        let data_defs = get_data_def(&matches).unwrap();
+        assert_eq!(data_defs.len(), 2);
+        assert_eq!(
+            DataDef::find_format(&data_defs, "date", "0"),
+            Some("%Y/%m/%d")
+        );
+
+        let (input_format, preset_defs) = get_input_format(&matches).unwrap();
+        assert!(input_format.field_names().contains(&"remote_addr"));
+        assert_eq!(
+            DataDef::find_format(&preset_defs, "timestamp", "time_local"),
+            Some("%d/%b/%Y:%H:%M:%S %z")
+        );
+        assert_eq!(DataDef::find_format(&preset_defs, "timestamp", "0"), None);
+
+        // the timestamp format of the command line overrides the one of the preset's time_local field
+        let data_defs = merge_data_defs(data_defs, preset_defs);
+        assert_eq!(
+            DataDef::find_format(&data_defs, "timestamp", "time_local"),
+            Some("%s")
+        );
+
+        let matches = App::from_yaml(yaml).get_matches_from(vec![
+            "semfilter",
+            "date(0) == 1900-01-01",
+            "-d",
+            "date",
+        ]);
+        assert!(get_data_def(&matches).is_err());
+
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec!["semfilter", "string(0) == x", "--format=iis"])
+            .is_err());
+        assert!(App::from_yaml(yaml)
+            .get_matches_from_safe(vec![
+                "semfilter",
+                "string(0) == x",
+                "--format=cri",
+                "--json"
+            ])
+            .is_err());
+    }
+
     #[test]
     fn misc_test() {}
 }
@@ -0,0 +1,674 @@
+use crate::cli::DataDef;
+use crate::grammar::{
+    is_ordered, is_wildcard, op_name, select_typed_tokens, selected_type, Literal, Rule,
+    ORDERING_OPS,
+};
+use crate::record::{resolve_index, Record};
+use crate::tokenizer::{parse_duration, HttpStatus, Token};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use lazy_static::lazy_static;
+use pest::iterators::Pair;
+use pest::prec_climber::{Assoc, Operator, PrecClimber};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+lazy_static! {
+    /// Multiplication and division take precedence over addition and subtraction.
+    static ref CLIMBER: PrecClimber<Rule> = {
+        PrecClimber::new(vec![
+            Operator::new(Rule::add_op, Assoc::Left) | Operator::new(Rule::sub_op, Assoc::Left),
+            Operator::new(Rule::mul_op, Assoc::Left) | Operator::new(Rule::div_op, Assoc::Left),
+        ])
+    };
+}
+
+/// The type of a value computed by an arithmetic expression or function call, known when the expression is
+/// parsed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueType {
+    Integer,
+    Number,
+    Date,
+    Timestamp,
+    Duration,
+    String,
+}
+
+impl ValueType {
+    /// Returns the type of the values of a type expression, only numbers, dates, timestamps, durations and text
+    /// can be computed with.
+    fn of(type_expr: &Pair<Rule>) -> Result<ValueType, String> {
+        match selected_type(type_expr)?.as_str() {
+            "integer" | "http_status" | "status" => Ok(ValueType::Integer),
+            "number" => Ok(ValueType::Number),
+            "date" => Ok(ValueType::Date),
+            "timestamp" => Ok(ValueType::Timestamp),
+            "duration" => Ok(ValueType::Duration),
+            "string" | "email" => Ok(ValueType::String),
+            _ => Err(format!(
+                "Invalid field {} in a computed expression, only integer, http_status, number, date, timestamp, duration, string and email fields can be computed with",
+                type_expr.as_str()
+            )),
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, ValueType::Integer | ValueType::Number)
+    }
+
+    /// Returns true if values of the types can be compared with each other.
+    fn is_comparable(self, other: ValueType) -> bool {
+        self == other || (self.is_numeric() && other.is_numeric())
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ValueType::Integer => "integer",
+            ValueType::Number => "number",
+            ValueType::Date => "date",
+            ValueType::Timestamp => "timestamp",
+            ValueType::Duration => "duration",
+            ValueType::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A value computed from the fields of a line, e.g. the duration between two timestamps.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Value {
+    Integer(i64),
+    Number(f64),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Duration(Duration),
+    String(String),
+}
+
+impl Value {
+    fn from_token(token: Token) -> Option<Value> {
+        match token {
+            Token::Integer(_, v) => i64::try_from(v).ok().map(Value::Integer),
+            Token::HttpStatus(_, HttpStatus::Code(v)) => Some(Value::Integer(i64::from(v))),
+            Token::Number(_, v) => Some(Value::Number(v)),
+            Token::Date(_, v, _) => Some(Value::Date(v)),
+            Token::Timestamp(_, v, _) => Some(Value::Timestamp(v)),
+            Token::Duration(_, v) => Some(Value::Duration(v)),
+            Token::String(_, v) | Token::Email(_, v) => Some(Value::String(v)),
+            _ => None,
+        }
+    }
+
+    /// Parses a value of the type, where a number is an integer unless it has decimals.
+    fn parse(value_type: ValueType, text: &str) -> Result<Value, String> {
+        match value_type {
+            ValueType::Integer | ValueType::Number => text
+                .parse::<i64>()
+                .map(Value::Integer)
+                .or_else(|_| text.parse::<f64>().map(Value::Number))
+                .map_err(|_| format!("Invalid number {}", text)),
+            ValueType::Duration => parse_duration(text).map(Value::Duration),
+            ValueType::String => Ok(Value::String(String::from(text))),
+            ValueType::Date | ValueType::Timestamp => {
+                Token::new(&value_type.to_string(), text, None, true)
+                    .ok()
+                    .and_then(Value::from_token)
+                    .ok_or_else(|| format!("Invalid {} {}", value_type, text))
+            }
+        }
+    }
+
+    fn value_type(&self) -> ValueType {
+        match self {
+            Value::Integer(_) => ValueType::Integer,
+            Value::Number(_) => ValueType::Number,
+            Value::Date(_) => ValueType::Date,
+            Value::Timestamp(_) => ValueType::Timestamp,
+            Value::Duration(_) => ValueType::Duration,
+            Value::String(_) => ValueType::String,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(v) => Some(*v as f64),
+            Value::Number(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Compares values of comparable types, integers and numbers are compared as numbers.
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Integer(_), Value::Number(_)) | (Value::Number(_), Value::Integer(_)) => {
+                self.as_f64()?.partial_cmp(&other.as_f64()?)
+            }
+            _ if self.value_type() == other.value_type() => self.partial_cmp(other),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the type of the result of an arithmetic operator applied to values of the types. Integers stay
+/// integers except when divided, the difference of two dates or timestamps is a duration and a duration can
+/// be added to or subtracted from them, multiplied or divided.
+fn result_type(lhs: ValueType, op_rule: Rule, rhs: ValueType) -> Result<ValueType, String> {
+    match (lhs, op_rule, rhs) {
+        (ValueType::Integer, Rule::add_op | Rule::sub_op | Rule::mul_op, ValueType::Integer) => {
+            Ok(ValueType::Integer)
+        }
+        (l, _, r) if l.is_numeric() && r.is_numeric() => Ok(ValueType::Number),
+        (ValueType::Date, Rule::sub_op, ValueType::Date)
+        | (ValueType::Timestamp, Rule::sub_op, ValueType::Timestamp) => Ok(ValueType::Duration),
+        (
+            ValueType::Date | ValueType::Timestamp,
+            Rule::add_op | Rule::sub_op,
+            ValueType::Duration,
+        ) => Ok(lhs),
+        (ValueType::Duration, Rule::add_op, ValueType::Date | ValueType::Timestamp) => Ok(rhs),
+        (ValueType::Duration, Rule::add_op | Rule::sub_op, ValueType::Duration) => {
+            Ok(ValueType::Duration)
+        }
+        (ValueType::Duration, Rule::div_op, ValueType::Duration) => Ok(ValueType::Number),
+        (ValueType::Duration, Rule::mul_op | Rule::div_op, r) if r.is_numeric() => {
+            Ok(ValueType::Duration)
+        }
+        (l, Rule::mul_op, ValueType::Duration) if l.is_numeric() => Ok(ValueType::Duration),
+        _ => Err(format!(
+            "Invalid operands {} and {} of {}",
+            lhs,
+            rhs,
+            op_symbol(op_rule)
+        )),
+    }
+}
+
+fn op_symbol(op_rule: Rule) -> &'static str {
+    match op_rule {
+        Rule::add_op => "+",
+        Rule::sub_op => "-",
+        Rule::mul_op => "*",
+        _ => "/",
+    }
+}
+
+/// Applies an arithmetic operator to two values, an error if the result overflows or a duration is divided
+/// by zero.
+fn apply(lhs: &Value, op_rule: Rule, rhs: &Value) -> Result<Value, String> {
+    let overflow = || format!("Overflow in {:?} {} {:?}", lhs, op_symbol(op_rule), rhs);
+    let nanos = |duration: Duration| -> f64 {
+        duration
+            .num_nanoseconds()
+            .map(|nanos| nanos as f64)
+            .unwrap_or(duration.num_milliseconds() as f64 * 1e6)
+    };
+    let scale = |duration: Duration, factor: f64| -> Result<Value, String> {
+        let scaled = nanos(duration) * factor;
+        if !scaled.is_finite() || scaled.abs() > i64::MAX as f64 {
+            return Err(overflow());
+        }
+        Ok(Value::Duration(
+            Duration::nanoseconds(scaled.round() as i64),
+        ))
+    };
+
+    match (lhs, op_rule, rhs) {
+        (Value::Integer(a), _, Value::Integer(b)) if op_rule != Rule::div_op => match op_rule {
+            Rule::add_op => a.checked_add(*b),
+            Rule::sub_op => a.checked_sub(*b),
+            _ => a.checked_mul(*b),
+        }
+        .map(Value::Integer)
+        .ok_or_else(overflow),
+        (Value::Integer(_) | Value::Number(_), _, Value::Integer(_) | Value::Number(_)) => {
+            let (a, b) = (lhs.as_f64().unwrap(), rhs.as_f64().unwrap());
+            Ok(Value::Number(match op_rule {
+                Rule::add_op => a + b,
+                Rule::sub_op => a - b,
+                Rule::mul_op => a * b,
+                _ => a / b,
+            }))
+        }
+        (Value::Date(a), Rule::sub_op, Value::Date(b)) => {
+            Ok(Value::Duration(a.signed_duration_since(*b)))
+        }
+        (Value::Timestamp(a), Rule::sub_op, Value::Timestamp(b)) => {
+            Ok(Value::Duration(a.signed_duration_since(*b)))
+        }
+        (Value::Date(a), Rule::add_op, Value::Duration(d))
+        | (Value::Duration(d), Rule::add_op, Value::Date(a)) => a
+            .checked_add_signed(*d)
+            .map(Value::Date)
+            .ok_or_else(overflow),
+        (Value::Date(a), Rule::sub_op, Value::Duration(d)) => a
+            .checked_sub_signed(*d)
+            .map(Value::Date)
+            .ok_or_else(overflow),
+        (Value::Timestamp(a), Rule::add_op, Value::Duration(d))
+        | (Value::Duration(d), Rule::add_op, Value::Timestamp(a)) => a
+            .checked_add_signed(*d)
+            .map(Value::Timestamp)
+            .ok_or_else(overflow),
+        (Value::Timestamp(a), Rule::sub_op, Value::Duration(d)) => a
+            .checked_sub_signed(*d)
+            .map(Value::Timestamp)
+            .ok_or_else(overflow),
+        (Value::Duration(a), Rule::add_op, Value::Duration(b)) => {
+            a.checked_add(b).map(Value::Duration).ok_or_else(overflow)
+        }
+        (Value::Duration(a), Rule::sub_op, Value::Duration(b)) => {
+            a.checked_sub(b).map(Value::Duration).ok_or_else(overflow)
+        }
+        (Value::Duration(a), Rule::div_op, Value::Duration(b)) => {
+            Ok(Value::Number(nanos(*a) / nanos(*b)))
+        }
+        (Value::Duration(d), Rule::mul_op, factor) | (factor, Rule::mul_op, Value::Duration(d))
+            if factor.as_f64().is_some() =>
+        {
+            scale(*d, factor.as_f64().unwrap())
+        }
+        (Value::Duration(d), Rule::div_op, divisor) if divisor.as_f64().is_some() => {
+            let divisor = divisor.as_f64().unwrap();
+            if divisor == 0.0 {
+                return Err(format!("Division of {} by zero", d));
+            }
+            scale(*d, 1.0 / divisor)
+        }
+        _ => result_type(lhs.value_type(), op_rule, rhs.value_type()).and(Err(overflow())),
+    }
+}
+
+/// Returns the type of the values of an arithmetic expression or operand, or an error naming the operands
+/// that can't be computed with.
+fn check_arith(pair: Pair<Rule>) -> Result<ValueType, String> {
+    match pair.as_rule() {
+        Rule::type_expr => ValueType::of(&pair),
+        Rule::duration_literal => parse_duration(pair.as_str()).map(|_| ValueType::Duration),
+        Rule::number_literal => {
+            Value::parse(ValueType::Number, pair.as_str()).map(|v| v.value_type())
+        }
+        Rule::string_literal => Ok(ValueType::String),
+        Rule::function_call => check_function(pair),
+        _ => {
+            let expr = pair.as_str();
+            CLIMBER.climb(pair.into_inner(), check_arith, |lhs, op, rhs| {
+                result_type(lhs?, op.as_rule(), rhs?).map_err(|e| format!("{} in {}", e, expr))
+            })
+        }
+    }
+}
+
+/// Returns the values of an arithmetic expression or operand. A type expression is replaced by each of its
+/// addressed tokens that is of the type, so that an expression has a value for every combination of them.
+fn eval_arith(
+    pair: Pair<Rule>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<Vec<Value>, String> {
+    match pair.as_rule() {
+        Rule::type_expr => Ok(select_typed_tokens(pair, record, data_defs)?
+            .into_iter()
+            .filter_map(|(_, token)| Value::from_token(token))
+            .collect()),
+        Rule::duration_literal => Ok(vec![Value::Duration(parse_duration(pair.as_str())?)]),
+        Rule::number_literal => Ok(vec![Value::parse(ValueType::Number, pair.as_str())?]),
+        Rule::string_literal => Ok(vec![Value::String(unquote(pair))]),
+        Rule::function_call => {
+            let mut inner = pair.into_inner();
+            let function = find_function(inner.next().unwrap().as_str())?;
+
+            // the function is called for every combination of the values of its arguments
+            let mut calls: Vec<Vec<Value>> = vec![Vec::new()];
+            for arg in inner {
+                let values = eval_arith(arg, record, data_defs)?;
+                calls = calls
+                    .iter()
+                    .flat_map(|args| {
+                        values.iter().map(move |value| {
+                            let mut args = args.clone();
+                            args.push(value.clone());
+                            args
+                        })
+                    })
+                    .collect();
+            }
+
+            Ok(calls
+                .iter()
+                .filter_map(|args| (function.call)(args))
+                .collect())
+        }
+        _ => CLIMBER.climb(
+            pair.into_inner(),
+            |operand| eval_arith(operand, record, data_defs),
+            |lhs, op, rhs| {
+                let (lhs, rhs, op_rule) = (lhs?, rhs?, op.as_rule());
+                lhs.iter()
+                    .flat_map(|l| rhs.iter().map(move |r| apply(l, op_rule, r)))
+                    .collect()
+            },
+        ),
+    }
+}
+
+/// A built-in scalar function called in an expression, e.g. len(string(7)).
+struct Function {
+    name: &'static str,
+    params: &'static [ValueType],
+    result: ValueType,
+    /// returns the result for arguments of the parameter types, None if there is none, e.g. for a part
+    /// that isn't in a split string
+    call: fn(&[Value]) -> Option<Value>,
+}
+
+/// The built-in scalar functions, also named in the function_name rule of the grammar. Text is counted and
+/// sliced by characters and a negative index counts from the end.
+const FUNCTIONS: [Function; 6] = [
+    Function {
+        name: "len",
+        params: &[ValueType::String],
+        result: ValueType::Integer,
+        call: len,
+    },
+    Function {
+        name: "lower",
+        params: &[ValueType::String],
+        result: ValueType::String,
+        call: lower,
+    },
+    Function {
+        name: "upper",
+        params: &[ValueType::String],
+        result: ValueType::String,
+        call: upper,
+    },
+    Function {
+        name: "trim",
+        params: &[ValueType::String],
+        result: ValueType::String,
+        call: trim,
+    },
+    Function {
+        name: "substr",
+        params: &[ValueType::String, ValueType::Integer, ValueType::Integer],
+        result: ValueType::String,
+        call: substr,
+    },
+    Function {
+        name: "split",
+        params: &[ValueType::String, ValueType::String, ValueType::Integer],
+        result: ValueType::String,
+        call: split,
+    },
+];
+
+fn find_function(name: &str) -> Result<&'static Function, String> {
+    FUNCTIONS
+        .iter()
+        .find(|function| function.name == name)
+        .ok_or_else(|| {
+            format!(
+                "Unknown function {}, the functions are: {}",
+                name,
+                FUNCTIONS
+                    .iter()
+                    .map(|function| function.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// Returns the result type of a function call if its arguments are of the parameter types.
+fn check_function(function_call: Pair<Rule>) -> Result<ValueType, String> {
+    let call = function_call.as_str();
+    let mut inner = function_call.into_inner();
+    let function = find_function(inner.next().unwrap().as_str())?;
+    let arg_types = inner.map(check_arith).collect::<Result<Vec<_>, _>>()?;
+
+    if arg_types != function.params {
+        return Err(format!(
+            "Invalid arguments of {}, expected {}({})",
+            call,
+            function.name,
+            function
+                .params
+                .iter()
+                .map(ValueType::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    Ok(function.result)
+}
+
+fn len(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s)] => i64::try_from(s.chars().count()).ok().map(Value::Integer),
+        _ => None,
+    }
+}
+
+fn lower(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s)] => Some(Value::String(s.to_lowercase())),
+        _ => None,
+    }
+}
+
+fn upper(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s)] => Some(Value::String(s.to_uppercase())),
+        _ => None,
+    }
+}
+
+fn trim(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s)] => Some(Value::String(String::from(s.trim()))),
+        _ => None,
+    }
+}
+
+/// Returns up to length characters from the start, e.g. substr(string(0), 0, 4) for the year of a date.
+fn substr(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s), Value::Integer(start), Value::Integer(length)] => {
+            let chars: Vec<char> = s.chars().collect();
+            let len = chars.len() as i64;
+            let start = if *start < 0 { len + start } else { *start }.clamp(0, len);
+            let end = start.saturating_add((*length).max(0)).min(len);
+            Some(Value::String(
+                chars[start as usize..end as usize].iter().collect(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the part at the index of the text split by the separator, e.g. split(string(3), ',', -1).
+fn split(args: &[Value]) -> Option<Value> {
+    match args {
+        [Value::String(s), Value::String(separator), Value::Integer(index)]
+            if !separator.is_empty() =>
+        {
+            let parts: Vec<&str> = s.split(separator.as_str()).collect();
+            let index = resolve_index(isize::try_from(*index).ok()?, parts.len())?;
+            Some(Value::String(String::from(parts[index])))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the text of a quoted literal, where \' is a quote.
+fn unquote(string_literal: Pair<Rule>) -> String {
+    string_literal
+        .into_inner()
+        .next()
+        .map(|text| text.as_str().replace("\\'", "'"))
+        .unwrap_or_default()
+}
+
+/// Checks the types of a comparison of an arithmetic expression when the expression is parsed, e.g. that the
+/// difference of two timestamps is compared with a duration like 5s.
+pub fn check_comparison(simple_expr: Pair<Rule>) -> Result<(), String> {
+    let mut inner = simple_expr.into_inner();
+    let arith = inner.next().unwrap();
+    let op_rule = inner.next().unwrap().into_inner().next().unwrap().as_rule();
+    let other = inner.next().unwrap();
+
+    let value_type = check_arith(arith.clone())?;
+
+    // the values are computed for every combination of the addressed tokens, which only stays linear in
+    // the number of tokens with at most one operand addressing more than one
+    let wildcards = arith
+        .clone()
+        .into_inner()
+        .flatten()
+        .filter(|pair| {
+            pair.as_rule() == Rule::type_expr
+                && is_wildcard(&pair.clone().into_inner().nth(1).unwrap())
+        })
+        .count();
+    if wildcards > 1 {
+        return Err(format!(
+            "Invalid expression {}, only one operand may address more than one token",
+            arith.as_str()
+        ));
+    }
+
+    if !ORDERING_OPS.contains(&op_rule) {
+        return Err(format!(
+            "Invalid operator {} for {}, only ==, !=, <, >, <= and >= compare computed values",
+            op_name(op_rule),
+            arith.as_str()
+        ));
+    }
+
+    match other.as_rule() {
+        Rule::field_expr => {
+            let field = other.into_inner().next().unwrap();
+            let other_type = ValueType::of(&field)?;
+            if value_type.is_comparable(other_type) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Invalid comparison of {} with {}, a {} can't be compared with a {}",
+                    arith.as_str(),
+                    field.as_str(),
+                    value_type,
+                    other_type
+                ))
+            }
+        }
+        _ => {
+            let literal = Literal::new(&other);
+            if !literal.flags.is_empty() {
+                return Err(format!(
+                    "Invalid flags {} of value {}, a computed value is compared as is",
+                    literal.flags, literal.text
+                ));
+            }
+            Value::parse(value_type, &literal.text)
+                .map(|_| ())
+                .map_err(|e| format!("{}, {} is a {}", e, arith.as_str(), value_type))
+        }
+    }
+}
+
+/// Evaluates a comparison of an arithmetic expression with a value or another field, true if any of the
+/// computed values compares as the operator requires.
+pub fn eval_comparison(
+    simple_expr: Pair<Rule>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<bool, String> {
+    let mut inner = simple_expr.into_inner();
+    let values = eval_arith(inner.next().unwrap(), record, data_defs)?;
+    let op_rule = inner.next().unwrap().into_inner().next().unwrap().as_rule();
+    let other = inner.next().unwrap();
+
+    let others = match (other.as_rule(), values.first()) {
+        (_, None) => return Ok(false),
+        (Rule::field_expr, _) => eval_arith(other.into_inner().next().unwrap(), record, data_defs)?,
+        (_, Some(value)) => vec![Value::parse(
+            value.value_type(),
+            &Literal::new(&other).text,
+        )?],
+    };
+
+    Ok(values.iter().any(|value| {
+        others
+            .iter()
+            .any(|other| is_ordered(op_rule, value.compare(other)))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expression::{apply, substr, Value};
+    use crate::grammar::Rule;
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(
+            apply(&Value::Integer(5), Rule::sub_op, &Value::Integer(7)).unwrap(),
+            Value::Integer(-2)
+        );
+        assert_eq!(
+            apply(&Value::Integer(5), Rule::div_op, &Value::Integer(2)).unwrap(),
+            Value::Number(2.5)
+        );
+        assert!(apply(&Value::Integer(i64::MAX), Rule::add_op, &Value::Integer(1)).is_err());
+
+        let date = NaiveDate::from_ymd(2026, 1, 31);
+        assert_eq!(
+            apply(
+                &Value::Date(date),
+                Rule::add_op,
+                &Value::Duration(Duration::days(1))
+            )
+            .unwrap(),
+            Value::Date(NaiveDate::from_ymd(2026, 2, 1))
+        );
+        assert_eq!(
+            apply(
+                &Value::Duration(Duration::seconds(3)),
+                Rule::mul_op,
+                &Value::Number(1.5)
+            )
+            .unwrap(),
+            Value::Duration(Duration::milliseconds(4500))
+        );
+        assert!(apply(
+            &Value::Duration(Duration::seconds(3)),
+            Rule::div_op,
+            &Value::Integer(0)
+        )
+        .is_err());
+        assert!(apply(&Value::Date(date), Rule::add_op, &Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn test_substr() {
+        let substr = |s: &str, start: i64, length: i64| match substr(&[
+            Value::String(String::from(s)),
+            Value::Integer(start),
+            Value::Integer(length),
+        ]) {
+            Some(Value::String(s)) => s,
+            _ => panic!("no substring"),
+        };
+
+        assert_eq!(substr("2026-01-02", 0, 4), "2026");
+        assert_eq!(substr("2026-01-02", -2, 2), "02");
+        assert_eq!(substr("Straße", 4, 10), "ße");
+        assert_eq!(substr("abc", 5, 1), "");
+        assert_eq!(substr("abc", -5, 2), "ab");
+        assert_eq!(substr("abc", 1, -1), "");
+    }
+}
@@ -1,9 +1,19 @@
-use crate::tokenizer::Token;
+use crate::cli::DataDef;
+use crate::expression::{check_comparison, eval_comparison};
+use crate::record::{resolve_index, PathSegment, Record};
+use crate::tokenizer::{Host, Token};
+use caseless::default_case_fold_str;
 use lazy_static::lazy_static;
 use log::trace;
 use pest::iterators::{Pair, Pairs};
 use pest::prec_climber::{Assoc, Operator, PrecClimber};
 use pest::Parser;
+use regex::Regex;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Mutex;
 
 #[derive(Parser)]
 #[grammar = "pest_grammar.pest"]
@@ -16,6 +26,10 @@ lazy_static! {
             Operator::new(Rule::and_op, Assoc::Left) | Operator::new(Rule::or_op, Assoc::Left),
         ])
     };
+
+    /// The regular expressions of the globs compared with like, so that a glob is translated once instead
+    /// of for each token of each line.
+    static ref GLOB_REGEXES: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
 }
 
 /// Public entry function to parse an expression using a list of tokens from the input.  
@@ -26,35 +40,372 @@ lazy_static! {
 ///
 /// parse_expression("date(1) in [1970-07-31, now()]", &tokens)
 /// ```
+#[allow(clippy::result_large_err)]
 pub fn parse_expression(expr: &str) -> Result<Pairs<'_, Rule>, pest::error::Error<Rule>> {
     SemFilterParser::parse(Rule::grammar, expr)
 }
 
+/// Parses a comma separated list of type expressions selecting the fields to print for a matching line.
+///
+/// # Examples
+/// ```
+/// parse_print_list("timestamp(0), ipv4(*), integer(8)")
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn parse_print_list(print: &str) -> Result<Pairs<'_, Rule>, pest::error::Error<Rule>> {
+    SemFilterParser::parse(Rule::print_list, print)
+}
+
+/// Selects the values of each type expression in a parsed print list from the tokens of a line. A type
+/// expression selects every addressed token that is of its type, e.g. `ipv4(*)` selects all IPv4 addresses
+/// in the line, a field without such a token is empty.
+pub fn select_fields(
+    print_list: &Pairs<Rule>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+
+    for field in select_typed_fields(print_list, record, data_defs)? {
+        if field.tokens.is_empty() {
+            values.push(String::new());
+        }
+        values.extend(
+            field
+                .tokens
+                .into_iter()
+                .map(|(token, _)| token.into_owned()),
+        );
+    }
+    Ok(values)
+}
+
+/// The tokens of a line addressed by a type expression that are of its type.
+#[derive(Debug)]
+pub struct TypedField<'t> {
+    /// the type expression as written, e.g. `integer(8)`
+    pub name: String,
+    /// true if the type expression can address more than one token, e.g. `integer(*)`
+    pub wildcard: bool,
+    /// the addressed tokens of the type, as found in the line and typed
+    pub tokens: Vec<(Cow<'t, str>, Token)>,
+}
+
+/// Selects the typed tokens of every type expression found in the parsed pairs, in the order they are
+/// written, e.g. for an expression or a print list.
+pub fn select_typed_fields<'t>(
+    pairs: &Pairs<Rule>,
+    record: &'t Record,
+    data_defs: &[DataDef],
+) -> Result<Vec<TypedField<'t>>, String> {
+    let mut fields = Vec::new();
+
+    for type_expr in pairs
+        .clone()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::type_expr)
+    {
+        fields.push(TypedField {
+            name: String::from(type_expr.as_str()),
+            wildcard: is_wildcard(&type_expr.clone().into_inner().nth(1).unwrap()),
+            tokens: select_typed_tokens(type_expr, record, data_defs)?,
+        });
+    }
+
+    Ok(fields)
+}
+
+/// Returns the tokens addressed by a type expression that are of its type, as found in the line and typed.
+pub fn select_typed_tokens<'t>(
+    type_expr: Pair<Rule>,
+    record: &'t Record,
+    data_defs: &[DataDef],
+) -> Result<Vec<(Cow<'t, str>, Token)>, String> {
+    let selected_type = selected_type(&type_expr)?;
+    let mut inner = type_expr.into_inner();
+    let type_term = inner.next().unwrap();
+    let type_term_arg = inner.next().unwrap();
+    let (mut format, mut component) = (None, None);
+    for pair in inner {
+        match pair.as_rule() {
+            Rule::format_expr => format = Some(pair.as_str()),
+            _ => component = Some(pair),
+        }
+    }
+    let format = format
+        .or_else(|| DataDef::find_format(data_defs, type_term.as_str(), type_term_arg.as_str()));
+
+    let tokens = select_tokens(type_term.as_str(), &type_term_arg, format, record)?;
+    let (tokens, format) = match component {
+        Some(component) => (
+            select_components(type_term.as_str(), format, &tokens, &component),
+            None,
+        ),
+        None => (tokens, format),
+    };
+
+    Ok(tokens
+        .into_iter()
+        .filter_map(|token| {
+            Token::new(&selected_type, &token, format, true)
+                .ok()
+                .map(|typed| (token, typed))
+        })
+        .collect())
+}
+
+/// Returns the type of the values a type expression selects, which is the type of its component if it has
+/// one, e.g. integer for url(6).port, or an error if the type has no such component.
+pub fn selected_type(type_expr: &Pair<Rule>) -> Result<String, String> {
+    let mut inner = type_expr.clone().into_inner();
+    let type_term = inner.next().unwrap();
+
+    match inner.find(|pair| pair.as_rule() == Rule::component) {
+        Some(component) => {
+            Token::component_type(type_term.as_str(), &component_path(&component)).map(String::from)
+        }
+        None => Ok(String::from(Token::type_name(type_term.as_str()))),
+    }
+}
+
+/// Returns the names of a component, e.g. query and user_id for .query.user_id.
+fn component_path<'i>(component: &Pair<'i, Rule>) -> Vec<&'i str> {
+    component
+        .clone()
+        .into_inner()
+        .map(|name| name.as_str())
+        .collect()
+}
+
+/// Returns the component of each token that is of the type and has it, e.g. the host of each URL.
+fn select_components<'t>(
+    type_term: &str,
+    format: Option<&str>,
+    tokens: &[Cow<str>],
+    component: &Pair<Rule>,
+) -> Vec<Cow<'t, str>> {
+    let path = component_path(component);
+    tokens
+        .iter()
+        .filter_map(|token| {
+            Token::new(type_term, token, format, true)
+                .ok()?
+                .component(&path)
+        })
+        .map(Cow::Owned)
+        .collect()
+}
+
+/// Checks the types of the comparisons in a parsed expression before any line is evaluated, i.e. that a field
+/// compared with another field is of the same type and the operands of an arithmetic expression can be
+/// computed with and compared, e.g. the arguments of a function call.
+pub fn check_expression(pairs: &Pairs<Rule>, data_defs: &[DataDef]) -> Result<(), String> {
+    for pair in pairs.clone().flatten() {
+        match pair.as_rule() {
+            Rule::type_expr => {
+                selected_type(&pair)?;
+            }
+            Rule::simple_expr if is_computed(&pair) => check_comparison(pair)?,
+            Rule::simple_expr | Rule::quantified_expr | Rule::count_expr => {
+                check_field_comparison(pair.clone())?;
+                check_values(pair, data_defs)?
+            }
+            Rule::range_expr | Rule::contains_expr => check_values(pair, data_defs)?,
+            // an index too large for isize would otherwise fail on every line
+            Rule::index => {
+                parse_index(&pair)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the values a field is compared with are values of its type, so that e.g. integer(*) == foo
+/// is reported once instead of failing on every line. Values of the text and match operators are any text.
+fn check_values(pair: Pair<Rule>, data_defs: &[DataDef]) -> Result<(), String> {
+    let is_count = pair.as_rule() == Rule::count_expr;
+    let inner: Vec<Pair<Rule>> = pair.into_inner().collect();
+    let field = inner
+        .iter()
+        .find(|pair| pair.as_rule() == Rule::type_expr)
+        .unwrap();
+    let value_type = selected_type(field)?;
+    let format = field_format(field, data_defs);
+    let check = |text: &str| Token::literal(&value_type, text, format).map(|_| ());
+
+    let mut op_rule = None;
+    for (i, child) in inner.iter().enumerate() {
+        match child.as_rule() {
+            Rule::op => op_rule = child.clone().into_inner().next().map(|op| op.as_rule()),
+            // the number of tokens a count is compared with
+            Rule::value if is_count && i == inner.len() - 1 => {
+                if !matches!(op_rule, Some(op_rule) if ORDERING_OPS.contains(&op_rule)) {
+                    return Err(format!(
+                        "Invalid operator {} for count, only ==, !=, <, >, <= and >= compare a count",
+                        op_name(op_rule.unwrap())
+                    ));
+                }
+                Token::literal("integer", &Literal::new(child).text, None)?;
+            }
+            Rule::value if matches!(op_rule, Some(op_rule) if ORDERING_OPS.contains(&op_rule)) => {
+                check(&Literal::new(child).text)?
+            }
+            Rule::value if op_rule == Some(Rule::subdomain_of_op) => {
+                Host::parse(&Literal::new(child).text)?;
+            }
+            Rule::value
+                if matches!(
+                    op_rule,
+                    Some(Rule::like_op)
+                        | Some(Rule::startswith_op)
+                        | Some(Rule::endswith_op)
+                        | Some(Rule::contains_op)
+                ) =>
+            {
+                text_operand(op_rule.unwrap(), &Literal::new(child))?;
+            }
+            Rule::list_expr => {
+                for value in child
+                    .clone()
+                    .into_inner()
+                    .flat_map(|members| members.into_inner())
+                {
+                    check(&Literal::new(&value).text)?
+                }
+            }
+            Rule::interval | Rule::closed_interval => {
+                for bound in child.clone().into_inner().filter(|bound| {
+                    matches!(bound.as_rule(), Rule::lower_bound | Rule::upper_bound)
+                }) {
+                    check(bound.as_str())?
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the format of a type expression, the format given in the expression or the default format of
+/// the type for the field. Components have no format.
+fn field_format<'a>(type_expr: &Pair<'a, Rule>, data_defs: &'a [DataDef]) -> Option<&'a str> {
+    let mut inner = type_expr.clone().into_inner();
+    let type_term = inner.next().unwrap();
+    let type_term_arg = inner.next().unwrap();
+
+    let mut format = None;
+    for pair in inner {
+        match pair.as_rule() {
+            Rule::format_expr => format = Some(pair.as_str()),
+            _ => return None,
+        }
+    }
+    format.or_else(|| DataDef::find_format(data_defs, type_term.as_str(), type_term_arg.as_str()))
+}
+
+/// Returns true if the left side of a simple expression is an arithmetic expression or a function call.
+fn is_computed(simple_expr: &Pair<Rule>) -> bool {
+    matches!(
+        simple_expr
+            .clone()
+            .into_inner()
+            .next()
+            .map(|pair| pair.as_rule()),
+        Some(Rule::arith_expr) | Some(Rule::function_call)
+    )
+}
+
+/// Checks that a type expression compared with another field is of the same type and compared by order.
+fn check_field_comparison(pair: Pair<Rule>) -> Result<(), String> {
+    let inner: Vec<Pair<Rule>> = pair.into_inner().collect();
+    let position = match inner
+        .iter()
+        .position(|pair| pair.as_rule() == Rule::field_expr)
+    {
+        Some(position) => position,
+        None => return Ok(()),
+    };
+    let left = inner
+        .iter()
+        .find(|pair| pair.as_rule() == Rule::type_expr)
+        .unwrap();
+    let op_rule = inner[position - 1]
+        .clone()
+        .into_inner()
+        .next()
+        .unwrap()
+        .as_rule();
+    let right = inner[position].clone().into_inner().next().unwrap();
+
+    if selected_type(left)? != selected_type(&right)? {
+        return Err(format!(
+            "Invalid comparison of {} with {}, both fields must be of the same type",
+            left.as_str(),
+            right.as_str()
+        ));
+    }
+    if !ORDERING_OPS.contains(&op_rule) {
+        return Err(format!(
+            "Invalid operator {} in comparison with {}, only ==, !=, <, >, <= and >= compare fields",
+            op_name(op_rule),
+            right.as_str()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the field names referenced by type expressions in the parsed pairs, e.g. `status` in
+/// `integer(status)`.
+pub fn referenced_names<'i>(pairs: &Pairs<'i, Rule>) -> Vec<&'i str> {
+    pairs
+        .clone()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::field_name)
+        .map(|pair| pair.as_str())
+        .collect()
+}
+
+/// Evaluates the expression for a line split into unnamed tokens.
+#[cfg(test)]
 pub fn evaluate_line(grammar: &mut Pairs<Rule>, tokens: &[&str]) -> Result<bool, String> {
-    process_grammar(grammar.next().unwrap(), &mut Vec::new(), tokens)
+    evaluate_record(grammar, &Record::from(tokens), &[])
+}
+
+/// Evaluates the expression for a line split into a record, which may have named tokens. A type expression
+/// without a format specifier uses the default format of its type in `data_defs`, if any.
+pub fn evaluate_record(
+    grammar: &mut Pairs<Rule>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<bool, String> {
+    process_grammar(grammar.next().unwrap(), &mut Vec::new(), record, data_defs)
 }
 
 /// Evaluates two tokens based on its infix operator and returns Result. Supported operators
-/// are defined in the grammar file.
+/// are defined in the grammar file. The result is None if the token isn't of the type.
 ///
 fn eval_op(
     type_term: &str,
-    op: Rule,
+    op_rule: Rule,
     value: Pair<Rule>,
     format: Option<&str>,
     token_val: &str,
-) -> Result<bool, String> {
+) -> Result<Option<bool>, String> {
     trace!(
         "op {:?}, value: {:?}, token {:?}, format {:?}",
-        op,
+        op_rule,
         value,
         token_val,
         format
     );
 
-    let process_match_op_token = |token: Token| -> Result<bool, String> {
+    let process_match_op_token = |token: Token, flags: &str| -> Result<bool, String> {
+        let literal = Literal::new(&value);
         match &token {
-            Token::String(_, _) => Ok(token.is_match(value.as_str())),
+            Token::String(_, _) => {
+                token.is_match(&literal.text, &format!("{}{}", literal.flags, flags))
+            }
             _ => Err(format!(
                 "Invalid token {}:{}, only string type allowed for match expr",
                 type_term, token_val
@@ -62,92 +413,568 @@ fn eval_op(
         }
     };
 
-    match Token::new_no_validation(type_term, token_val, format) {
+    // the text operators compare the value of a string or email, case folded if the value has the i flag
+    let process_text_op_token = |token: &Token| -> Result<bool, String> {
+        if !matches!(
+            token,
+            Token::String(_, _) | Token::Email(_, _) | Token::Host(_, _)
+        ) {
+            return Err(format!(
+                "Invalid token {}:{}, only string, email and host types allowed for {}",
+                type_term,
+                token_val,
+                op_name(op_rule)
+            ));
+        }
+        let literal = Literal::new(&value);
+        let text = text_operand(op_rule, &literal)?;
+        let v = match literal.flags {
+            "i" => default_case_fold_str(&token.get_value()),
+            _ => token.get_value(),
+        };
+        match op_rule {
+            Rule::startswith_op => Ok(v.starts_with(&text)),
+            Rule::endswith_op => Ok(v.ends_with(&text)),
+            Rule::contains_op => Ok(v.contains(&text)),
+            _ => glob_regex(&text).map(|regex| regex.is_match(&v)),
+        }
+    };
+
+    // compares the token with a value, case-insensitively if the value has the i flag
+    let compare = |token: &Token, value: &Pair<Rule>| -> Result<Option<Ordering>, String> {
+        let literal = Literal::new(value);
+        let other = token.copy(&literal.text, format)?;
+        match literal.flags {
+            "" => Ok(token.compare_literal(&other)),
+            "i" => Ok(token.fold_case()?.compare_literal(&other.fold_case()?)),
+            flags => Err(format!(
+                "Invalid flags {} of value {}, only i is allowed in a comparison",
+                flags, literal.text
+            )),
+        }
+    };
+
+    let is_member = |token: &Token, list: Pair<Rule>| -> Result<bool, String> {
+        for member in list.into_inner() {
+            if compare(token, &member)? == Some(Ordering::Equal) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+
+    // the bounds of a between expression are included, the bounds of an interval if in square brackets
+    let in_range =
+        |token: &Token, range: Pair<Rule>| -> Result<bool, String> {
+            let range_str = range.as_str();
+            let parts: Vec<Pair<Rule>> = range.into_inner().collect();
+            let (lower_included, lower, upper, upper_included) = match parts.as_slice() {
+                [lower, upper] => (true, lower, upper, true),
+                [start, lower, upper, end] => {
+                    (start.as_str() == "[", lower, upper, end.as_str() == "]")
+                }
+                _ => return Err(format!("Invalid range {}", range_str)),
+            };
+            let lower = token.compare_literal(&token.copy(lower.as_str(), format)?);
+            let upper = token.compare_literal(&token.copy(upper.as_str(), format)?);
+
+            Ok((lower == Some(Ordering::Greater)
+                || (lower_included && lower == Some(Ordering::Equal)))
+                && (upper == Some(Ordering::Less)
+                    || (upper_included && upper == Some(Ordering::Equal))))
+        };
+
+    let contains = |token: &Token, value: Pair<Rule>| -> Result<bool, String> {
+        match value.as_rule() {
+            Rule::interval => in_range(token, value),
+            _ => is_member(token, value),
+        }
+    };
+
+    match Token::new(type_term, token_val, format, true) {
         Ok(token) => {
-            match op {
-                Rule::eq => Ok(token == token.copy(value.as_str(), format)?),
-                Rule::neq => Ok(token != token.copy(value.as_str(), format)?),
-                Rule::lt => Ok(token < token.copy(value.as_str(), format)?),
-                Rule::gt => Ok(token > token.copy(value.as_str(), format)?),
-                Rule::lte => Ok(token <= token.copy(value.as_str(), format)?),
-                Rule::gte => Ok(token >= token.copy(value.as_str(), format)?),
-                Rule::match_op => process_match_op_token(token),
-                Rule::in_op => Ok(value
-                    .into_inner()
-                    .any(|r| token.copy(r.as_str(), format).unwrap() == token)),
-                Rule::not_in_op => Ok(!value
-                    .into_inner()
-                    .any(|r| token.copy(r.as_str(), format).unwrap() == token)),
+            match op_rule {
+                Rule::eq | Rule::neq | Rule::lt | Rule::gt | Rule::lte | Rule::gte => {
+                    Ok(is_ordered(op_rule, compare(&token, &value)?))
+                }
+                Rule::match_op => process_match_op_token(token, ""),
+                Rule::imatch_op => process_match_op_token(token, "i"),
+                Rule::like_op | Rule::startswith_op | Rule::endswith_op | Rule::contains_op => {
+                    process_text_op_token(&token)
+                }
+                Rule::subdomain_of_op => match &token {
+                    Token::Host(_, host) => Host::parse(&Literal::new(&value).text)
+                        .map(|domain| host.is_subdomain_of(&domain)),
+                    _ => Err(format!(
+                        "Invalid token {}:{}, only host type allowed for subdomain_of",
+                        type_term, token_val
+                    )),
+                },
+                Rule::in_op => contains(&token, value),
+                Rule::not_in_op => Ok(!contains(&token, value)?),
+                Rule::between_op => in_range(&token, value),
 
                 // no match is fine, just move to the next token...
                 _ => Ok(false),
             }
+            .map(Some)
+        }
+        // a token that is not of the type is not compared, just move to the next token...
+        Err(_) => Ok(None),
+    }
+}
+
+/// Compares a token with the tokens of another field of the same type, true if it is ordered as the
+/// operator requires with any of them. The result is None if the token isn't of the type.
+fn eval_field_op(
+    type_term: &str,
+    op_rule: Rule,
+    format: Option<&str>,
+    token_val: &str,
+    others: &[Token],
+) -> Result<Option<bool>, String> {
+    trace!(
+        "op {:?}, fields: {:?}, token {:?}",
+        op_rule,
+        others,
+        token_val
+    );
+
+    match Token::new(type_term, token_val, format, true) {
+        Ok(token) => {
+            Ok(Some(others.iter().any(|other| {
+                is_ordered(op_rule, token.compare_value(other))
+            })))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Returns true if the ordering of a token and the value it is compared with satisfies the operator, which
+/// is one of the ORDERING_OPS.
+pub fn is_ordered(op_rule: Rule, ordering: Option<Ordering>) -> bool {
+    match op_rule {
+        Rule::eq => ordering == Some(Ordering::Equal),
+        Rule::neq => ordering != Some(Ordering::Equal),
+        Rule::lt => ordering == Some(Ordering::Less),
+        Rule::gt => ordering == Some(Ordering::Greater),
+        Rule::lte => matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+        Rule::gte => matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal)),
+        _ => false,
+    }
+}
+
+/// The operators that compare tokens by their order, the only ones allowed between two fields.
+pub const ORDERING_OPS: [Rule; 6] = [
+    Rule::eq,
+    Rule::neq,
+    Rule::lt,
+    Rule::gt,
+    Rule::lte,
+    Rule::gte,
+];
+
+/// Returns the operator as written in an expression, e.g. startswith for Rule::startswith_op.
+pub fn op_name(op_rule: Rule) -> String {
+    format!("{:?}", op_rule).trim_end_matches("_op").to_string()
+}
+
+/// Returns the text a value is compared with by a text operator, case folded if the value has the i flag.
+/// The glob of like is translated too, so that an invalid value is reported before any line is read.
+fn text_operand(op_rule: Rule, literal: &Literal) -> Result<String, String> {
+    let text = match literal.flags {
+        "" => literal.text.to_string(),
+        "i" => default_case_fold_str(&literal.text),
+        flags => {
+            return Err(format!(
+                "Invalid flags {} of value {}, only i is allowed with {}",
+                flags,
+                literal.text,
+                op_name(op_rule)
+            ))
+        }
+    };
+    if op_rule == Rule::like_op {
+        glob_regex(&text)?;
+    }
+    Ok(text)
+}
+
+/// Returns the regular expression of a glob, translated once and then taken from GLOB_REGEXES.
+fn glob_regex(glob: &str) -> Result<Regex, String> {
+    let mut regexes = GLOB_REGEXES.lock().unwrap();
+    if let Some(regex) = regexes.get(glob) {
+        return Ok(regex.clone());
+    }
+
+    let regex =
+        Regex::new(&glob_to_regex(glob)).map_err(|e| format!("Invalid pattern {}: {}", glob, e))?;
+    regexes.insert(String::from(glob), regex.clone());
+    Ok(regex)
+}
+
+/// Translates a glob to an anchored regular expression, where * matches any text, ? any character and
+/// a backslash escapes the next character, e.g. conn*refused matches connection refused.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?s)^");
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// A value compared with a token, where a quoted value may contain any character and be followed by
+/// flags, e.g. 'Connection refused'i.
+pub struct Literal<'a> {
+    pub text: Cow<'a, str>,
+    pub flags: &'a str,
+}
+
+impl<'a> Literal<'a> {
+    pub fn new(value: &Pair<'a, Rule>) -> Literal<'a> {
+        match value.clone().into_inner().next() {
+            Some(quoted_value) => {
+                let mut parts = quoted_value.into_inner();
+                let text = parts.next().map(|text| text.as_str()).unwrap_or_default();
+                Literal {
+                    text: if text.contains("\\'") {
+                        Cow::Owned(text.replace("\\'", "'"))
+                    } else {
+                        Cow::Borrowed(text)
+                    },
+                    flags: parts.next().map(|flags| flags.as_str()).unwrap_or_default(),
+                }
+            }
+            None => Literal {
+                text: Cow::Borrowed(value.as_str()),
+                flags: "",
+            },
+        }
+    }
+}
+
+/// A type expression and the operator and value it is compared with, popped from the evaluation stack.
+struct Comparison<'a> {
+    type_term: Pair<'a, Rule>,
+    type_term_arg: Pair<'a, Rule>,
+    format: Option<&'a str>,
+    /// the component compared instead of the token, e.g. .host in url(6).host
+    component: Option<Pair<'a, Rule>>,
+    /// the operator and the value, list of values or other field, None for a type expression that is only
+    /// counted
+    op_value: Option<(Rule, Pair<'a, Rule>)>,
+}
+
+impl<'a> Comparison<'a> {
+    fn pop(stack: &mut Vec<Pair<'a, Rule>>) -> Comparison<'a> {
+        let top = |stack: &Vec<Pair<Rule>>| stack.last().map(|pair| pair.as_rule());
+
+        let op_value = match top(stack) {
+            Some(Rule::value)
+            | Some(Rule::field_expr)
+            | Some(Rule::list_member_expr)
+            | Some(Rule::interval)
+            | Some(Rule::closed_interval) => {
+                let value = stack.pop().unwrap(); // simple value or comma separated value string....
+                let op = stack.pop().unwrap();
+                Some((op.as_rule(), value))
+            }
+            _ => None,
+        };
+
+        let component = match top(stack) {
+            Some(Rule::component) => stack.pop(),
+            _ => None,
+        };
+
+        // the grammar will parse date(n) and date(n, format), if the latter the stack will have one additional element
+        let format = match top(stack) {
+            Some(Rule::format_expr) => Some(stack.pop().unwrap().as_str()),
+            _ => None,
+        };
+
+        let type_term_arg = stack.pop().unwrap(); // n in type(n)
+        let type_term = stack.pop().unwrap(); // date, time, timestamp, email, ...
+
+        Comparison {
+            type_term,
+            type_term_arg,
+            format,
+            component,
+            op_value,
+        }
+    }
+
+    /// Returns the format specifier or the default format of the type for the type_term_arg, if any.
+    fn format<'c>(&'c self, data_defs: &'c [DataDef]) -> Option<&'c str> {
+        self.format.or_else(|| {
+            DataDef::find_format(
+                data_defs,
+                self.type_term.as_str(),
+                self.type_term_arg.as_str(),
+            )
+        })
+    }
+
+    /// Returns the tokens of the record addressed by the type_term_arg, or their components if any.
+    fn select_tokens<'t>(
+        &self,
+        record: &'t Record,
+        data_defs: &[DataDef],
+    ) -> Result<Vec<Cow<'t, str>>, String> {
+        let type_term = self.type_term.as_str();
+        let format = self.format(data_defs);
+        let tokens = select_tokens(type_term, &self.type_term_arg, format, record)?;
+
+        Ok(match &self.component {
+            Some(component) => select_components(type_term, format, &tokens, component),
+            None => tokens,
+        })
+    }
+
+    /// Returns the type the tokens are compared as and its format, the type of the component if any.
+    fn compared_type<'c>(
+        &'c self,
+        data_defs: &'c [DataDef],
+    ) -> Result<(&'c str, Option<&'c str>), String> {
+        match &self.component {
+            Some(component) => Ok((
+                Token::component_type(self.type_term.as_str(), &component_path(component))?,
+                None,
+            )),
+            None => Ok((self.type_term.as_str(), self.format(data_defs))),
         }
-        // no match is fine, just move to the next token...
-        Err(_) => Ok(false),
+    }
+
+    /// Returns the typed tokens of the other field the tokens are compared with, None if compared with a
+    /// value. Both fields must be of the same type and compared by their order, see [check_expression].
+    fn select_field_tokens(
+        &self,
+        record: &Record,
+        data_defs: &[DataDef],
+    ) -> Result<Option<Vec<Token>>, String> {
+        let field = match &self.op_value {
+            Some((_, value)) if value.as_rule() == Rule::field_expr => {
+                value.clone().into_inner().next().unwrap()
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(
+            select_typed_tokens(field, record, data_defs)?
+                .into_iter()
+                .map(|(_, token)| token)
+                .collect(),
+        ))
+    }
+
+    /// Returns the result of the comparison for each addressed token that is of the type, a type expression
+    /// without comparison is true for each of them.
+    fn eval_tokens<'c>(
+        &'c self,
+        tokens: &'c [Cow<str>],
+        record: &Record,
+        data_defs: &'c [DataDef],
+    ) -> Result<impl Iterator<Item = Result<bool, String>> + 'c, String> {
+        let (type_term, format) = self.compared_type(data_defs)?;
+        let others = self.select_field_tokens(record, data_defs)?;
+
+        Ok(tokens
+            .iter()
+            .map(move |token| match (&self.op_value, &others) {
+                (Some((op, _)), Some(others)) => {
+                    eval_field_op(type_term, *op, format, token, others)
+                }
+                (Some((op, value)), None) => eval_op(type_term, *op, value.clone(), format, token),
+                (None, _) => Ok(Token::new(type_term, token, format, true)
+                    .ok()
+                    .map(|_| true)),
+            })
+            .filter_map(Result::transpose))
     }
 }
 
 /// Evaluates a tokenized string expression against a set of rules derived from the semfile grammar
 /// [pest_grammar.pest](pest_grammar.pest)
-fn eval(stack: &mut Vec<Pair<Rule>>, rule: Rule, tokens: &[&str]) -> Result<bool, String> {
+fn eval(
+    stack: &mut Vec<Pair<Rule>>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<bool, String> {
     trace!("eval.stack: {:?}", stack);
 
-    let value = stack.pop().unwrap(); // simple value or comma separated value string....
-    let op = stack.pop().unwrap();
+    let comparison = Comparison::pop(stack);
 
-    // the grammar will parse date(n) and date(n, format), if the latter the stack will have one additional element
-    let format: Option<&str> = if stack.len() == 3 {
-        Some(stack.pop().unwrap().as_str())
-    } else {
-        None
+    // the comparison is true if any token matches unless a quantifier says otherwise
+    let quantifier_rule = match stack.last().map(|pair| pair.as_rule()) {
+        Some(Rule::any_quantifier) | Some(Rule::all_quantifier) | Some(Rule::none_quantifier) => {
+            stack.pop().unwrap().as_rule()
+        }
+        _ => Rule::any_quantifier,
     };
 
-    let type_term_arg = stack.pop().unwrap(); // n in type(n)
-    let type_term = stack.pop().unwrap(); // date, time, timestamp, email, ...
-
     // find n'th (type_term_arg) typeTerm among the token whose type == type_term..
-    trace!("type_term.as_str {:?}", type_term.as_str());
-
-    match type_term_arg.as_str() {
-        "*" => {
-            // eval all tokens to see if any matches the type_term, e.g:
-            //   true for: date(*) == 1900-01-01    for tokens:[1970-07-31, 1900-01-01]
-            //   false for: string(*) == mikael   for tokens:[test, 42]
-            Ok(tokens.iter().any(|t| {
-                eval_op(type_term.as_str(), op.as_rule(), value.clone(), format, t).unwrap()
-            }))
-        }
-        // otherwise if not an asterix its a zero-based index to a word in the current row...
-        index => {
-            match index.parse::<usize>().unwrap() {
-                index if tokens.is_empty() || tokens.len() - 1 < index => Ok(false),
-
-                index => {
-                    match rule {
-                        // eval the token that matched  the type_term, e.g:
-                        //   true for: date(1) == 1900-01-01    for tokens:[1900-01-01, 1970-07-31]
-                        //   false for: date(2) == 1900-01-01   for tokens:[1900-01-01, 1970-07-31]
-                        Rule::simple_expr => eval_op(
-                            type_term.as_str(),
-                            op.as_rule(),
-                            value,
-                            format,
-                            tokens[index],
-                        ),
-                        Rule::contains_expr => eval_op(
-                            type_term.as_str(),
-                            op.as_rule(),
-                            value,
-                            format,
-                            tokens[index],
-                        ),
-                        _ => Err(String::from("Unexpected rule matched!")),
+    trace!("type_term.as_str {:?}", comparison.type_term.as_str());
+
+    // eval the tokens addressed by the type_term_arg that are of the type, e.g:
+    //   true for: date(*) == 1900-01-01    for tokens:[1970-07-31, 1900-01-01]
+    //   false for: string(*) == mikael   for tokens:[test, 42]
+    //   true for: date(1) == 1900-01-01    for tokens:[1970-07-31, 1900-01-01]
+    //   false for: date(2) == 1900-01-01   for tokens:[1970-07-31, 1900-01-01]
+    //   false for: all(date(*)) > 1950-01-01   for tokens:[1970-07-31, 1900-01-01]
+    let tokens = comparison.select_tokens(record, data_defs)?;
+    let mut results = comparison.eval_tokens(&tokens, record, data_defs)?;
+    let mut any = |expected: bool| -> Result<bool, String> {
+        for result in &mut results {
+            if result? == expected {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+
+    match quantifier_rule {
+        Rule::all_quantifier => Ok(!any(false)?),
+        Rule::none_quantifier => Ok(!any(true)?),
+        _ => any(true),
+    }
+}
+
+/// Counts the addressed tokens that are of the type and match the comparison, if any.
+fn count_tokens(
+    stack: &mut Vec<Pair<Rule>>,
+    record: &Record,
+    data_defs: &[DataDef],
+) -> Result<usize, String> {
+    let comparison = Comparison::pop(stack);
+    let tokens = comparison.select_tokens(record, data_defs)?;
+
+    let mut count = 0;
+    for result in comparison.eval_tokens(&tokens, record, data_defs)? {
+        if result? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Returns the tokens addressed by a type_term_arg, i.e. all tokens for an asterix, the token at the
+/// zero-based index or counted from the end if negative, the n-th token that is of the type, the tokens in
+/// an index range, the token with the field name, the value of a `key=value` pair or the values at a JSON
+/// path. None if the line has fewer tokens or no such name, key or path.
+fn select_tokens<'t>(
+    type_term: &str,
+    type_term_arg: &Pair<Rule>,
+    format: Option<&str>,
+    record: &'t Record,
+) -> Result<Vec<Cow<'t, str>>, String> {
+    let arg = type_term_arg.clone().into_inner().next().unwrap();
+
+    match arg.as_rule() {
+        Rule::wildcard => Ok(record
+            .tokens
+            .iter()
+            .map(|token| Cow::Borrowed(token.as_ref()))
+            .collect()),
+        Rule::index => Ok(resolve_index(parse_index(&arg)?, record.tokens.len())
+            .map(|index| Cow::Borrowed(record.tokens[index].as_ref()))
+            .into_iter()
+            .collect()),
+        Rule::typed_index => {
+            let index = parse_index(&arg.into_inner().next().unwrap())?;
+            let typed: Vec<&str> = record
+                .tokens
+                .iter()
+                .map(|token| token.as_ref())
+                .filter(|token| Token::new(type_term, token, format, true).is_ok())
+                .collect();
+
+            Ok(resolve_index(index, typed.len())
+                .map(|index| Cow::Borrowed(typed[index]))
+                .into_iter()
+                .collect())
+        }
+        Rule::index_range => Ok(record.tokens[parse_range(&arg, record.tokens.len())?]
+            .iter()
+            .map(|token| Cow::Borrowed(token.as_ref()))
+            .collect()),
+        Rule::field_name => Ok(record.field(arg.as_str()).into_iter().collect()),
+        Rule::kv_field => {
+            let key = arg.into_inner().next().unwrap();
+            Ok(record.kv(key.as_str()).into_iter().collect())
+        }
+        Rule::json_path => {
+            let path = arg
+                .into_inner()
+                .map(|segment| match segment.as_rule() {
+                    Rule::json_key => Ok(PathSegment::Key(segment.as_str())),
+                    _ => {
+                        let index = segment.into_inner().next().unwrap();
+                        match index.as_rule() {
+                            Rule::wildcard => Ok(PathSegment::Wildcard),
+                            _ => parse_index(&index).map(PathSegment::Index),
+                        }
                     }
-                }
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            Ok(record.path(&path))
+        }
+        _ => Err(format!("Unexpected type argument {}", arg.as_str())),
+    }
+}
+
+fn parse_index(index: &Pair<Rule>) -> Result<isize, String> {
+    index
+        .as_str()
+        .parse::<isize>()
+        .map_err(|e| format!("Invalid index {}: {}", index.as_str(), e))
+}
+
+/// Returns the positions of an index range in a sequence of `len` elements, i.e. `3..6` is 3, 4 and 5,
+/// `3..=6` includes 6 and an open start or end is the first or last element. Negative indices count from
+/// the end and the range is clamped to the elements.
+fn parse_range(index_range: &Pair<Rule>, len: usize) -> Result<Range<usize>, String> {
+    let clamp = |index: isize| resolve_index(index, len).unwrap_or(if index < 0 { 0 } else { len });
+    let (mut start, mut end, mut inclusive) = (0, len, false);
+
+    for part in index_range.clone().into_inner() {
+        match part.as_rule() {
+            Rule::range_start => start = clamp(parse_index(&part.into_inner().next().unwrap())?),
+            Rule::range_op => inclusive = part.as_str() == "..=",
+            _ => {
+                let index = parse_index(&part.into_inner().next().unwrap())?;
+                end = match (inclusive, index) {
+                    // up to and including the last element
+                    (true, -1) => len,
+                    (true, index) => clamp(index.saturating_add(1)),
+                    (false, index) => clamp(index),
+                };
             }
         }
     }
+
+    Ok(start..end.max(start))
+}
+
+/// True if the type_term_arg can address more than one token, e.g. `*`, `2..5` or `.tags[*]`.
+pub fn is_wildcard(type_term_arg: &Pair<Rule>) -> bool {
+    type_term_arg
+        .clone()
+        .into_inner()
+        .flatten()
+        .any(|arg| matches!(arg.as_rule(), Rule::wildcard | Rule::index_range))
 }
 
 /// Internal function that processes a pest grammar pair and evaluates to Result.
@@ -159,9 +986,10 @@ fn eval(stack: &mut Vec<Pair<Rule>>, rule: Rule, tokens: &[&str]) -> Result<bool
 fn process_grammar<'a>(
     pair: Pair<'a, Rule>,
     stack: &mut Vec<Pair<'a, Rule>>,
-    tokens: &[&str],
+    record: &Record,
+    data_defs: &[DataDef],
 ) -> Result<bool, String> {
-    let atom = |pair| process_grammar(pair, stack, tokens);
+    let atom = |pair| process_grammar(pair, stack, record, data_defs);
 
     let infix = |lhs: Result<bool, String>,
                  op: Pair<Rule>,
@@ -177,11 +1005,11 @@ fn process_grammar<'a>(
     };
 
     let process_tokens =
-        |stack: &mut Vec<Pair<'a, Rule>>, tokens, pair: Pair<'a, Rule>| -> Result<bool, String> {
+        |stack: &mut Vec<Pair<'a, Rule>>, record, pair: Pair<'a, Rule>| -> Result<bool, String> {
             pair.into_inner()
-                .map(|pair| process_grammar(pair, stack, tokens))
+                .map(|pair| process_grammar(pair, stack, record, data_defs))
                 .count();
-            eval(stack, Rule::simple_expr, tokens)
+            eval(stack, record, data_defs)
         };
 
     //let inner_rule = pair.clone();
@@ -191,22 +1019,52 @@ fn process_grammar<'a>(
         Rule::expr => {
             return CLIMBER.climb(pair.into_inner(), atom, infix);
         }
+        Rule::simple_expr if is_computed(&pair) => {
+            return eval_comparison(pair, record, data_defs);
+        }
         Rule::simple_expr => {
-            return process_tokens(stack, tokens, pair);
+            return process_tokens(stack, record, pair);
         }
         Rule::contains_expr => {
-            return process_tokens(stack, tokens, pair);
+            return process_tokens(stack, record, pair);
         }
+        Rule::quantified_expr => {
+            return process_tokens(stack, record, pair);
+        }
+        Rule::range_expr => {
+            return process_tokens(stack, record, pair);
+        }
+        Rule::count_expr => {
+            let mut inner: Vec<Pair<Rule>> = pair.into_inner().collect();
+            let value = inner.pop().unwrap();
+            let op = inner.pop().unwrap().into_inner().next().unwrap();
+
+            inner.into_iter().map(atom).count();
+            let count = count_tokens(stack, record, data_defs)?;
+
+            // the count is compared as an integer
+            return Ok(
+                eval_op("integer", op.as_rule(), value, None, &count.to_string())?.unwrap_or(false),
+            );
+        }
+        Rule::any_quantifier => stack.push(pair),
+        Rule::all_quantifier => stack.push(pair),
+        Rule::none_quantifier => stack.push(pair),
         Rule::type_expr => {
             pair.into_inner().map(atom).count();
         }
         Rule::type_term => stack.push(pair),
         Rule::type_term_arg => stack.push(pair),
         Rule::format_expr => stack.push(pair),
+        Rule::component => stack.push(pair),
         Rule::op => stack.push(pair.into_inner().next().unwrap()),
         Rule::in_op => stack.push(pair),
         Rule::not_in_op => stack.push(pair),
+        Rule::between_op => stack.push(pair),
+        Rule::interval => stack.push(pair),
+        Rule::closed_interval => stack.push(pair),
         Rule::value => stack.push(pair),
+        Rule::field_expr => stack.push(pair),
         Rule::list_expr => {
             pair.into_inner().map(atom).count();
         }
@@ -240,7 +1098,13 @@ mod tests {
     //     assert!(result.is_ok())
     // }
 
-    use crate::grammar::{evaluate_line, parse_expression};
+    use crate::cli::DataDef;
+    use crate::grammar::{
+        check_expression, evaluate_line, evaluate_record, parse_expression, parse_print_list,
+        referenced_names, select_fields, select_typed_fields,
+    };
+    use crate::record::{split_json, split_tokens, Record};
+    use regex::Regex;
     use std::io::Write;
 
     fn init() {
@@ -250,6 +1114,11 @@ mod tests {
             .try_init();
     }
 
+    /// Evaluates the expression for a line split into the tokens.
+    fn eval(expr: &str, tokens: &[&str]) -> Result<bool, String> {
+        evaluate_line(&mut parse_expression(expr).unwrap(), tokens)
+    }
+
     #[test]
     fn test_empty_tokens() {
         assert!(
@@ -269,7 +1138,8 @@ mod tests {
 
     #[test]
     fn test_invalid_index_type() {
-        assert!(parse_expression("date(blaha) == 1970/07/31").is_err());
+        // a word is a field name but not a valid index
+        assert!(parse_expression("date(bl@ha) == 1970/07/31").is_err());
     }
 
     #[test]
@@ -446,4 +1316,669 @@ mod tests {
         )
         .unwrap());
     }
+
+    #[test]
+    fn test_print_list() {
+        assert!(parse_print_list("timestamp(0), ipv4(*), integer(8)").is_ok());
+        assert!(parse_print_list("date(0, %Y/%m/%d)").is_ok());
+        assert!(parse_print_list("date(0) == 1970-07-31").is_err());
+        assert!(parse_print_list("").is_err());
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let tokens = [
+            "2026-01-01T12:00:00Z",
+            "GET",
+            "10.0.0.1",
+            "127.0.0.1",
+            "200",
+        ];
+        let print_list = parse_print_list("timestamp(0), ipv4(*), integer(4), date(1)").unwrap();
+
+        assert_eq!(
+            select_fields(&print_list, &Record::from(&tokens[..]), &[]).unwrap(),
+            vec!["2026-01-01T12:00:00Z", "10.0.0.1", "127.0.0.1", "200", ""]
+        );
+        assert_eq!(
+            select_fields(&print_list, &Record::default(), &[]).unwrap(),
+            vec!["", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn test_select_typed_fields() {
+        let tokens = ["1970-07-31", "42", "test", "1900-01-01"];
+        let grammar =
+            parse_expression("(date(*) > 1950-01-01 || integer(1) == 42) && string(9) == x")
+                .unwrap();
+        let record = Record::from(&tokens[..]);
+        let fields = select_typed_fields(&grammar, &record, &[]).unwrap();
+
+        assert_eq!(
+            fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+            vec!["date(*)", "integer(1)", "string(9)"]
+        );
+        assert!(fields[0].wildcard);
+        assert_eq!(fields[0].tokens.len(), 2);
+        assert_eq!(fields[1].tokens[0].0, "42");
+        assert!(fields[2].tokens.is_empty());
+    }
+
+    #[test]
+    fn test_field_names() {
+        let tokens = ["2026-01-02", "500"];
+        let record = Record::from(&tokens[..]).with_names(vec!["created_at", "status"]);
+        let expr = "date(created_at) > 2026-01-01 && integer(status) >= 500";
+
+        assert!(evaluate_record(&mut parse_expression(expr).unwrap(), &record, &[]).unwrap());
+        assert!(!evaluate_record(
+            &mut parse_expression("integer(missing) == 500").unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+        assert_eq!(
+            referenced_names(&parse_expression(expr).unwrap()),
+            vec!["created_at", "status"]
+        );
+        assert!(parse_expression("integer(1st) == 500").is_err());
+    }
+
+    #[test]
+    fn test_json_path() {
+        let record = split_json(
+            r#"{"ts": "2026-01-02T10:00:00Z", "status": 503, "request": {"user": {"id": "u1"}}, "tags": ["db", "timeout"]}"#,
+        )
+        .unwrap();
+        let expr = "string(.request.user.id) == u1 && integer(.status) >= 500 && timestamp(.ts) > 2026-01-01";
+
+        assert!(evaluate_record(&mut parse_expression(expr).unwrap(), &record, &[]).unwrap());
+        assert!(evaluate_record(
+            &mut parse_expression("string(.tags[*]) == timeout && string(.tags[0]) == db").unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+        assert!(!evaluate_record(
+            &mut parse_expression("string(.tags[*]) == cache").unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+        assert!(evaluate_record(
+            &mut parse_expression("integer(status) == 503").unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+
+        let fields = select_typed_fields(
+            &parse_print_list("string(.tags[*]), integer(.status)").unwrap(),
+            &record,
+            &[],
+        )
+        .unwrap();
+        assert!(fields[0].wildcard);
+        assert!(!fields[1].wildcard);
+        assert!(referenced_names(&parse_expression(expr).unwrap()).is_empty());
+
+        assert!(parse_expression("string(.) == x").is_err());
+        assert!(parse_expression("string(.tags[x]) == x").is_err());
+    }
+
+    #[test]
+    fn test_kv_field() {
+        let line =
+            r#"2026-01-02 10:00:00 ERROR request failed status=503 user="bob smith" dur=512ms"#;
+        let record = split_tokens(line, &Regex::new(" ").unwrap());
+
+        assert!(evaluate_record(
+            &mut parse_expression("integer(kv:status) >= 500 && string(kv:user) match ^bob")
+                .unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+        assert!(!evaluate_record(
+            &mut parse_expression("integer(kv:code) >= 500").unwrap(),
+            &record,
+            &[]
+        )
+        .unwrap());
+        assert!(referenced_names(&parse_expression("integer(kv:status) == 1").unwrap()).is_empty());
+        assert!(parse_expression("integer(kv:) == 1").is_err());
+    }
+
+    #[test]
+    fn test_negative_index_and_range() {
+        let tokens = ["GET", "/api", "200", "12", "512", "slow"];
+
+        assert!(eval("string(-1) == slow", &tokens).unwrap());
+        assert!(eval("integer(-2) == 512", &tokens).unwrap());
+        assert!(!eval("string(-7) == GET", &tokens).unwrap());
+        assert!(eval("integer(2..4) == 12", &tokens).unwrap());
+        assert!(!eval("integer(2..4) == 512", &tokens).unwrap());
+        assert!(eval("integer(2..=4) == 512", &tokens).unwrap());
+        assert!(eval("integer(3..) > 500", &tokens).unwrap());
+        assert!(!eval("string(..2) == 200", &tokens).unwrap());
+        assert!(eval("integer(-3..-1) == 12", &tokens).unwrap());
+        assert!(eval("string(..=-1) == slow", &tokens).unwrap());
+        assert!(!eval("string(4..2) == slow", &tokens).unwrap());
+        assert!(!eval("string(9..) == slow", &tokens).unwrap());
+        assert!(eval("string(..=9223372036854775807) == slow", &tokens).unwrap());
+        assert!(evaluate_line(&mut parse_expression("string(-1) == x").unwrap(), &[]).is_ok());
+
+        let record = Record::from(&tokens[..]);
+        let fields = select_typed_fields(
+            &parse_print_list("integer(2..), integer(-1)").unwrap(),
+            &record,
+            &[],
+        )
+        .unwrap();
+        assert!(fields[0].wildcard);
+        assert_eq!(fields[0].tokens.len(), 3);
+        assert!(!fields[1].wildcard);
+        assert!(fields[1].tokens.is_empty());
+
+        assert!(parse_expression("string(--1) == x").is_err());
+        assert!(parse_expression("string(1...) == x").is_err());
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        let tokens = ["GET", "10.0.0.1", "200", "12", "10.0.0.2", "INFO"];
+
+        assert!(eval("all(integer(*)) < 1000", &tokens).unwrap());
+        assert!(!eval("all(integer(*)) < 100", &tokens).unwrap());
+        assert!(eval("any(integer(*)) < 100", &tokens).unwrap());
+        assert!(eval("none(string(*)) == DEBUG", &tokens).unwrap());
+        assert!(!eval("none(string(*)) in [INFO, DEBUG]", &tokens).unwrap());
+        assert!(eval("all(loglevel(*)) >= INFO", &tokens).unwrap());
+        // no token of the type
+        assert!(eval("all(date(*)) > 2000-01-01", &tokens).unwrap());
+        assert!(eval(
+            "all(ipv4(1..3)) == 10.0.0.1 && none(ipv4(-1)) == 10.0.0.2",
+            &tokens
+        )
+        .unwrap());
+
+        assert!(eval("count(ipv4(*)) >= 2", &tokens).unwrap());
+        assert!(!eval("count(ipv4(*)) > 2", &tokens).unwrap());
+        assert!(eval("count(integer(*) > 100) == 1", &tokens).unwrap());
+        assert!(eval("count(string(*) in [GET, INFO, POST]) == 2", &tokens).unwrap());
+        assert!(eval("count(date(*)) == 0 && count(integer(9)) == 0", &tokens).unwrap());
+        assert!(eval("count(ipv4(*)) >= many", &tokens).is_err());
+        assert!(eval("all(integer(*)) < many", &tokens).is_err());
+
+        assert!(parse_expression("all(integer(*) < 1000)").is_err());
+        assert!(parse_expression("count(ipv4(*))").is_err());
+        assert!(parse_expression("some(ipv4(*)) == 10.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_typed_index() {
+        let tokens = [
+            "proxy",
+            "10.0.0.1",
+            "->",
+            "10.0.0.2",
+            "2026-01-01",
+            "took",
+            "12",
+            "ms",
+            "2026-01-02",
+        ];
+
+        assert!(eval("ipv4(#0) == 10.0.0.1", &tokens).unwrap());
+        assert!(eval("ipv4(#1) == 10.0.0.2", &tokens).unwrap());
+        assert!(eval("ipv4(#-1) == 10.0.0.2 && ipv4(#-2) == 10.0.0.1", &tokens).unwrap());
+        assert!(!eval("ipv4(#2) == 10.0.0.2", &tokens).unwrap());
+        assert!(eval("date(#1) == 2026-01-02", &tokens).unwrap());
+        assert!(!eval("date(1) == 2026-01-02", &tokens).unwrap());
+        assert!(eval("date(#0, %Y-%m-%d) == 2026-01-01", &tokens).unwrap());
+        assert!(eval("integer(#0) == 12", &tokens).unwrap());
+        assert!(eval("count(ipv4(#1)) == 1", &tokens).unwrap());
+
+        let record = Record::from(&tokens[..]);
+        assert_eq!(
+            select_fields(
+                &parse_print_list("ipv4(#1), date(#-1)").unwrap(),
+                &record,
+                &[]
+            )
+            .unwrap(),
+            vec!["10.0.0.2", "2026-01-02"]
+        );
+        assert!(parse_expression("ipv4(#) == 10.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let tokens = [
+            "10.0.0.17",
+            "2026-01-02T10:00:00Z",
+            "GET",
+            "1.4.2",
+            "200",
+            "fe80::1",
+            "2.5",
+        ];
+
+        assert!(eval("integer(4) between 200 and 299", &tokens).unwrap());
+        assert!(eval("integer(4) between 100 and 200", &tokens).unwrap());
+        assert!(!eval("integer(4) between 201 and 299", &tokens).unwrap());
+        assert!(eval("integer(4) in [200..300)", &tokens).unwrap());
+        assert!(!eval("integer(4) in (200..300)", &tokens).unwrap());
+        assert!(!eval("integer(4) in [100..200)", &tokens).unwrap());
+        assert!(eval("integer(4) in [100..200]", &tokens).unwrap());
+        assert!(eval("integer(4) !in [300..600)", &tokens).unwrap());
+        assert!(!eval("integer(4) between 299 and 200", &tokens).unwrap());
+
+        assert!(eval("ipv4(0) between 10.0.0.0 and 10.0.0.255", &tokens).unwrap());
+        assert!(eval("ipv4(0) in [10.0.0.0..10.0.1.0)", &tokens).unwrap());
+        assert!(!eval("ipv4(0) in [10.0.1.0..10.0.2.0)", &tokens).unwrap());
+        assert!(eval("ipv6(5) in [fe80::..fe80::ffff]", &tokens).unwrap());
+        assert!(eval("timestamp(1) in [2026-01-02..2026-01-03)", &tokens).unwrap());
+        assert!(eval("date(*) between 2026-01-01 and 2026-12-31", &tokens).is_ok());
+        assert!(eval("semver(3) between 1.2.0 and 1.10.0", &tokens).unwrap());
+        assert!(eval("number(6) in (2.4..2.6)", &tokens).unwrap());
+        assert!(eval("string(2) between A and Z", &tokens).unwrap());
+        assert!(eval("all(integer(*)) in [0..1000)", &tokens).unwrap());
+        assert!(eval("count(integer(*) between 100 and 299) == 1", &tokens).unwrap());
+        assert!(eval("integer(4) between 200 and many", &tokens).is_err());
+
+        assert!(parse_expression("integer(4) between 200").is_err());
+        assert!(parse_expression("integer(4) in [200..300").is_err());
+        assert!(parse_expression("integer(4) in [200, 300]").is_ok());
+    }
+
+    #[test]
+    fn test_check_values() {
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+
+        assert!(check("integer(*) == 42").is_ok());
+        assert!(check("date(0, %d/%m/%Y) == 31/07/1970 && date(1) < now()").is_ok());
+        assert!(check(r"string(0) match '\d+' || string(1) like 'a*'").is_ok());
+        assert!(check("count(ipv4(*) in [127.0.0.1, 10.0.0.1]) >= 2").is_ok());
+        assert!(check("integer(*) == foo").is_err());
+        assert!(check("any(integer(*)) > 1.5.0").is_err());
+        assert!(check("integer(0) in [1, x]").is_err());
+        assert!(check("semver(0) between 1.0.0 and latest").is_err());
+        assert!(check("integer(0) in [200..x)").is_err());
+        assert!(check("count(integer(*)) == many").is_err());
+        assert!(check("count(integer(*)) match 2").is_err());
+        assert!(check("count(string(*) match x) != 2").is_ok());
+        assert!(check("integer(99999999999999999999) == 5").is_err());
+        assert!(check("integer(#-99999999999999999999) == 5").is_err());
+        assert!(check("integer(1..99999999999999999999) == 5").is_err());
+        assert!(check("string(.tags[99999999999999999999]) == x").is_err());
+        assert!(check("string(0) like 'conn*refused'i").is_ok());
+        assert!(check("string(0) like 'conn*refused'x").is_err());
+        assert!(check("string(0) startswith 'conn'm").is_err());
+
+        let data_defs = [DataDef::for_field("date", "%d/%m/%Y", "0")];
+        assert!(check_expression(
+            &parse_expression("date(0) == 31/07/1970").unwrap(),
+            &data_defs
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_validated_types() {
+        let tokens = ["mikael@gmail.com", "not-an-email", "127.0.0.1", "1.2.3"];
+
+        // tokens are validated, a token that isn't of the type is never compared
+        assert!(eval("count(email(*)) == 1", &tokens).unwrap());
+        assert!(!eval("email(1) == not-an-email", &tokens).unwrap());
+        assert!(eval("count(semver(*)) == 1", &tokens).unwrap());
+
+        // the grammar names the types ipv4 and ipv6
+        assert!(eval("ipv4(2) == 127.0.0.1", &tokens).unwrap());
+        assert!(!eval("ipv6(2) == ::1", &tokens).unwrap());
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let tokens = [
+            "ERROR",
+            "Straße",
+            "Bob@Example.com",
+            "Connection refused",
+            "200",
+        ];
+
+        assert!(!eval("string(0) == error", &tokens).unwrap());
+        assert!(eval("string(0) == 'error'i", &tokens).unwrap());
+        assert!(!eval("string(0) != 'Error'i", &tokens).unwrap());
+        assert!(eval("string(0) in [warn, 'error'i]", &tokens).unwrap());
+        assert!(eval("string(0) !in ['warn'i, 'info'i]", &tokens).unwrap());
+        assert!(eval("string(1) == 'STRASSE'i", &tokens).unwrap());
+        assert!(eval("email(2) == 'bob@example.com'i", &tokens).unwrap());
+        assert!(eval("string(3) == 'Connection refused'", &tokens).unwrap());
+        assert!(eval("string(0) imatch ^err", &tokens).unwrap());
+        assert!(!eval("string(0) match ^err", &tokens).unwrap());
+        assert!(eval("string(3) match 'connection\\s+REFUSED'i", &tokens).unwrap());
+        assert!(eval(
+            "string(3) match 'connection \\s refused # a comment'ix",
+            &tokens
+        )
+        .unwrap());
+        assert!(eval("any(string(*)) == 'it\\'s'", &tokens).is_ok());
+
+        // flags other than i are only allowed with match, case folding only for strings and emails
+        assert!(eval("string(0) == 'error's", &tokens).is_err());
+        assert!(eval("integer(4) == '200'i", &tokens).is_err());
+        assert!(parse_expression("string(0) == 'error'q").is_err());
+    }
+
+    #[test]
+    fn test_text_operators() {
+        let tokens = [
+            "connection",
+            "alice@ourcorp.com",
+            "Bob@OurCorp.com",
+            "/api/v2/users",
+            "200",
+        ];
+
+        assert!(eval("string(0) like 'conn*'", &tokens).unwrap());
+        assert!(eval("string(0) like 'c?nnection'", &tokens).unwrap());
+        assert!(!eval("string(0) like 'conn'", &tokens).unwrap());
+        assert!(eval("string(0) like 'CONN*'i", &tokens).unwrap());
+        assert!(eval("string(3) like '/api/*/users'", &tokens).unwrap());
+        assert!(!eval("string(3) like '/api/\\*/users'", &tokens).unwrap());
+        assert!(eval("string(3) startswith /api/v2/", &tokens).unwrap());
+        assert!(!eval("string(3) startswith /v2/", &tokens).unwrap());
+        assert!(eval("string(3) endswith users", &tokens).unwrap());
+        assert!(eval("string(3) contains v2", &tokens).unwrap());
+        assert!(!eval("all(email(*)) endswith @ourcorp.com", &tokens).unwrap());
+        assert!(eval("all(email(*)) endswith '@ourcorp.com'i", &tokens).unwrap());
+        assert!(eval("count(email(*) contains alice) == 1", &tokens).unwrap());
+
+        assert!(eval("integer(4) startswith 2", &tokens).is_err());
+        assert!(eval("string(0) contains 'conn's", &tokens).is_err());
+    }
+
+    #[test]
+    fn test_field_comparisons() {
+        let tokens = [
+            "2026-01-05",
+            "10.0.0.1",
+            "10.0.0.2",
+            "2026-01-03",
+            "512",
+            "2048",
+            "03/01/2026",
+        ];
+
+        assert!(eval("date(0) > date(3)", &tokens).unwrap());
+        assert!(!eval("date(0) < date(3)", &tokens).unwrap());
+        assert!(eval("date(3) == date(6, %d/%m/%Y)", &tokens).unwrap());
+        assert!(eval("integer(5) > integer(4)", &tokens).unwrap());
+        assert!(eval("integer(4) <= integer(4)", &tokens).unwrap());
+        assert!(eval("ipv4(1) != ipv4(2)", &tokens).unwrap());
+        assert!(eval("ipv4(1) < ipv4(*)", &tokens).unwrap());
+        assert!(eval("all(date(*)) >= date(3)", &tokens).unwrap());
+        assert!(eval("count(integer(*) < integer(5)) == 1", &tokens).unwrap());
+        assert!(eval("integer(5) > integer(4) && date(0) > date(3)", &tokens).unwrap());
+
+        // a field that isn't of the type compares with nothing
+        assert!(!eval("integer(4) < integer(1)", &tokens).unwrap());
+
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+        assert!(check("date(0) < date(3) && all(integer(*)) > integer(4)").is_ok());
+        assert!(check("date(0) < integer(4)").is_err());
+        assert!(check("count(date(*) > integer(4)) == 1").is_err());
+        assert!(check("string(1) contains string(2)").is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let tokens = [
+            "2026-01-02T10:00:00Z",
+            "2026-01-02T10:00:07Z",
+            "512",
+            "2048",
+            "0.25",
+            "2026-01-05",
+            "2026-01-02",
+        ];
+
+        assert!(eval("integer(3) - integer(2) > 1000", &tokens).unwrap());
+        assert!(eval("integer(2) - integer(3) == -1536", &tokens).unwrap());
+        assert!(eval("integer(2) + integer(3) * 2 == 4608", &tokens).unwrap());
+        assert!(eval("(integer(2) + integer(3)) * 2 == 5120", &tokens).unwrap());
+        assert!(eval("integer(3) / integer(2) == 4", &tokens).unwrap());
+        assert!(eval("integer(2) / 1000 < 0.6", &tokens).unwrap());
+        assert!(eval("number(4) * 1000 >= 250", &tokens).unwrap());
+        assert!(eval("timestamp(1) - timestamp(0) > 5s", &tokens).unwrap());
+        assert!(!eval("timestamp(1) - timestamp(0) > 1m", &tokens).unwrap());
+        assert!(eval("timestamp(1) - timestamp(0) == 7000ms", &tokens).unwrap());
+        assert!(eval("timestamp(0) + 7s == timestamp(1)", &tokens).unwrap());
+        assert!(eval("timestamp(0) + 1h > 2026-01-02T10:30:00Z", &tokens).unwrap());
+        assert!(eval("date(5) - date(6) >= 3d", &tokens).unwrap());
+        assert!(eval("(timestamp(1) - timestamp(0)) * 2 == 14s", &tokens).unwrap());
+        assert!(eval("(timestamp(1) - timestamp(0)) / 1s == 7", &tokens).unwrap());
+        assert!(eval("integer(3) - integer(2) > integer(2)", &tokens).unwrap());
+        assert!(eval("integer(*) * 2 > 4000", &tokens).unwrap());
+        assert!(!eval("integer(0) + 1 > 0", &tokens).unwrap());
+
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+        assert!(check("timestamp(1) - timestamp(0) > 5s").is_ok());
+        assert!(check("integer(3) - integer(2) > 1000 || number(4) * 2 < integer(3)").is_ok());
+        assert!(check("timestamp(1) - timestamp(0) > 5").is_err());
+        assert!(check("timestamp(1) + timestamp(0) > 5s").is_err());
+        assert!(check("timestamp(1) - integer(2) > 5s").is_err());
+        assert!(check("string(1) + 1 > 5").is_err());
+        assert!(check("integer(3) - integer(2) > 5s").is_err());
+        assert!(check("integer(3) - integer(2) > date(5)").is_err());
+        assert!(check("integer(3) - integer(2) match 5").is_err());
+        assert!(check("integer(*) * 2 > integer(1..)").is_ok());
+        assert!(check("integer(*) + integer(2..) > 5").is_err());
+    }
+
+    #[test]
+    fn test_functions() {
+        let tokens = [
+            "2026-01-02",
+            "ERROR",
+            "key1,key2,key3",
+            "  padded ",
+            "Straße",
+            "bob@example.com",
+        ];
+
+        assert!(eval("len(string(4)) == 6", &tokens).unwrap());
+        assert!(eval("len(string(2)) > 10", &tokens).unwrap());
+        assert!(eval("lower(string(1)) == error", &tokens).unwrap());
+        assert!(eval("upper(string(4)) == STRASSE", &tokens).unwrap());
+        assert!(eval("trim(string(3)) == padded", &tokens).unwrap());
+        assert!(eval("substr(string(0), 0, 4) == 2026", &tokens).unwrap());
+        assert!(eval("substr(string(0), -2, 2) == 02", &tokens).unwrap());
+        assert!(eval("split(string(2), ',', 1) == key2", &tokens).unwrap());
+        assert!(eval("split(string(2), ',', -1) == key3", &tokens).unwrap());
+        assert!(!eval("split(string(2), ',', 3) == key3", &tokens).unwrap());
+        assert!(eval("split(email(5), '@', 1) == example.com", &tokens).unwrap());
+        assert!(eval("len(trim(string(3))) + 1 == 7", &tokens).unwrap());
+        assert!(eval("len(string(*)) > 12", &tokens).unwrap());
+        assert!(parse_expression("lower(string(1)) == lower(string(1))").is_err());
+
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+        assert!(check("substr(string(0), 0, 4) == 2026 && len(string(7)) > 200").is_ok());
+        assert!(check("len(integer(0)) > 2").is_err());
+        assert!(check("len(string(0), 1) > 2").is_err());
+        assert!(check("substr(string(0), '0', 4) == 2026").is_err());
+        assert!(parse_expression("size(string(0)) > 2").is_err());
+        assert!(check("len(string(0)) > abc").is_err());
+        assert!(check("lower(string(0)) == 'error'i").is_err());
+        assert!(check("lower(string(0)) + 1 == 2").is_err());
+        assert!(check("substr(string(*), integer(*), 4) == 2026").is_err());
+    }
+
+    #[test]
+    fn test_durations() {
+        let tokens = [
+            "GET",
+            "512ms",
+            "1.5s",
+            "PT5M",
+            "00:01:02.345",
+            "2m30s",
+            "200",
+        ];
+
+        assert!(eval("duration(1) < 1s", &tokens).unwrap());
+        assert!(eval("duration(2) == 1500ms", &tokens).unwrap());
+        assert!(eval("duration(3) == 300s", &tokens).unwrap());
+        assert!(eval("duration(4) > 1m", &tokens).unwrap());
+        assert!(eval("duration(4) between 1m and PT2M", &tokens).unwrap());
+        assert!(eval("duration(5) == 00:02:30", &tokens).unwrap());
+        assert!(eval("duration(*) > 500ms", &tokens).unwrap());
+        assert!(eval("all(duration(*)) > 500ms", &tokens).unwrap());
+        assert!(!eval("all(duration(*)) > 1s", &tokens).unwrap());
+        assert!(eval("count(duration(*)) == 5", &tokens).unwrap());
+        assert!(eval("duration(5) > duration(2)", &tokens).unwrap());
+        assert!(eval("duration(5) - duration(2) == 2m28.5s", &tokens).unwrap());
+        assert!(eval("duration(2) * 2 == 3s", &tokens).unwrap());
+        assert!(!eval("duration(0) > 1s", &tokens).unwrap());
+        assert!(eval("duration(1) < 1", &tokens).is_err());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let tokens = ["GET", "15000000", "10K", "2GiB", "3.5MB"];
+
+        assert!(eval("bytes(1) > 10MB", &tokens).unwrap());
+        assert!(!eval("bytes(1) > 15MB", &tokens).unwrap());
+        assert!(eval("bytes(2) == 10240", &tokens).unwrap());
+        assert!(eval("bytes(2) == 10KiB", &tokens).unwrap());
+        assert!(eval("bytes(3) > 2GB", &tokens).unwrap());
+        assert!(eval("bytes(4) between 3MiB and 4MB", &tokens).unwrap());
+        assert!(eval("bytes(4) == '3500 kB'", &tokens).unwrap());
+        assert!(eval("bytes(3) > bytes(1)", &tokens).unwrap());
+        assert!(eval("count(bytes(*) >= 1K) == 4", &tokens).unwrap());
+        assert!(eval("bytes(1) > 10X", &tokens).is_err());
+    }
+
+    #[test]
+    fn test_urls() {
+        let tokens = [
+            "GET",
+            "https://API.example.com/v2/users?user_id=42&q=a%20b",
+            "/v2/caf%C3%A9",
+            "key:value",
+            "http://[::1]:8080/health",
+        ];
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+
+        assert!(eval("url(1).host == api.example.com", &tokens).unwrap());
+        assert!(eval("url(1).scheme == https", &tokens).unwrap());
+        assert!(eval("url(1).path startswith /v2/", &tokens).unwrap());
+        assert!(eval("url(1).query.user_id == 42", &tokens).unwrap());
+        assert!(eval("url(1).query.q == 'a b'", &tokens).unwrap());
+        assert!(!eval("url(1).query.id == 42", &tokens).unwrap());
+        assert!(eval("url(1).port == 443", &tokens).unwrap());
+        assert!(eval("url(2).path == '/v2/café'", &tokens).unwrap());
+        assert!(!eval("url(2).host == localhost", &tokens).unwrap());
+        assert!(!eval("url(3) == /v2/x", &tokens).unwrap());
+        assert!(!eval("url(3).path == key:value", &tokens).unwrap());
+        assert!(eval("count(url(*).path startswith /v2/) == 2", &tokens).unwrap());
+        // the host is a string, so it can be matched and be an IP address
+        assert!(eval("url(1).host match api", &tokens).unwrap());
+        assert!(eval("url(4).host == '[::1]'", &tokens).unwrap());
+        assert!(eval("url(4).port == 8080", &tokens).unwrap());
+
+        assert!(check("url(1).host == url(2).host").is_ok());
+        assert!(check("url(0).user == x").is_err());
+        assert!(check("integer(0).host == x").is_err());
+        assert!(check("url(1).port == string(2)").is_err());
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let tokens = [
+            "request",
+            "123e4567-e89b-12d3-a456-426614174000",
+            "00-1A-2B-3C-4D-5E",
+            "span=00f067aa0ba902b7",
+            "00F067AA0BA902B7",
+        ];
+
+        assert!(eval("uuid(*) == 123E4567-E89B-12D3-A456-426614174000", &tokens).unwrap());
+        assert!(eval("uuid(1) == '{123e4567e89b12d3a456426614174000}'", &tokens).unwrap());
+        assert!(!eval("uuid(1) == 123e4567-e89b-12d3-a456-426614174001", &tokens).unwrap());
+        assert!(eval("mac(*) in [001a.2b3c.4d5e, 00:00:5e:00:53:af]", &tokens).unwrap());
+        assert!(eval("mac(2) == 00:1a:2b:3c:4d:5e", &tokens).unwrap());
+        assert!(!eval("mac(*) in [00:00:5e:00:53:af]", &tokens).unwrap());
+        assert!(eval("hex(4) == 0x00f067aa0ba902b7", &tokens).unwrap());
+        assert!(eval("hex(4) != 00f067aa0ba902b8", &tokens).unwrap());
+        assert!(eval("count(hex(*)) == 1", &["200", "1.2.3", "00F067AA0BA902B7"]).unwrap());
+        assert!(eval(
+            "count(uuid(*) == 123e4567-e89b-12d3-a456-426614174000) == 1",
+            &tokens
+        )
+        .unwrap());
+        assert!(eval("uuid(1) == not-a-uuid", &tokens).is_err());
+    }
+
+    #[test]
+    fn test_http_status() {
+        let tokens = ["GET", "/api/users", "503", "404", "5xx"];
+
+        assert!(eval("status(2) == 5xx", &tokens).unwrap());
+        assert!(!eval("status(3) == 5xx", &tokens).unwrap());
+        assert!(eval("status(2) in [4xx, 503]", &tokens).unwrap());
+        assert!(eval("status(3) in [4xx, 503]", &tokens).unwrap());
+        assert!(eval("status(2) >= 500", &tokens).unwrap());
+        assert!(eval("status(3) < 5xx", &tokens).unwrap());
+        assert!(eval("http_status(3) == not_found", &tokens).unwrap());
+        assert!(eval("http_status(2) == server_error", &tokens).unwrap());
+        assert!(eval("status(3) between 4xx and 5xx", &tokens).unwrap());
+        assert!(eval("status(2) - status(3) == 99", &tokens).unwrap());
+        assert!(eval("count(status(*) >= 4xx) == 2", &tokens).unwrap());
+        assert!(eval("status(2) == teapot", &tokens).is_err());
+        assert!(eval("status(4) == 5xx", &tokens).is_ok());
+        assert!(!eval("status(4) == 5xx", &tokens).unwrap());
+
+        let tokens = ["503", "503", "404"];
+        assert!(eval("status(0) == http_status(1)", &tokens).unwrap());
+        assert!(eval("http_status(2) < status(0)", &tokens).unwrap());
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+        assert!(check("status(0) == http_status(1)").is_ok());
+    }
+
+    #[test]
+    fn test_hosts() {
+        let tokens = [
+            "connect",
+            "DB1.Internal.Example.com.",
+            "jane@Gmail.com",
+            "https://api.example.com/v2",
+            "-invalid-",
+        ];
+        let check = |expr: &str| check_expression(&parse_expression(expr).unwrap(), &[]);
+
+        assert!(eval("host(1) == db1.internal.example.com", &tokens).unwrap());
+        assert!(eval("host(*) subdomain_of example.com", &tokens).unwrap());
+        assert!(eval("host(1) subdomain_of Internal.Example.com.", &tokens).unwrap());
+        assert!(!eval("host(1) subdomain_of ample.com", &tokens).unwrap());
+        assert!(eval("host(*) == '*.internal.example.com'", &tokens).unwrap());
+        assert!(!eval("host(1) == '*.example.com'", &tokens).unwrap());
+        assert!(eval("host(1) != '*.example.com'", &tokens).unwrap());
+        assert!(!eval("host(1) > '*.example.com'", &tokens).unwrap());
+        assert!(eval("host(1) in ['*.*.example.com', localhost]", &tokens).unwrap());
+        assert!(eval("host(1) endswith .example.com", &tokens).unwrap());
+        assert!(!eval("host(4) == -invalid-", &tokens).unwrap());
+        assert!(eval("email(*).domain == gmail.com", &tokens).unwrap());
+        assert!(eval("email(*).domain subdomain_of com", &tokens).unwrap());
+        assert!(eval("string(0) subdomain_of example.com", &tokens).is_err());
+        assert!(eval("host(1) subdomain_of '*.com'", &tokens).is_err());
+        assert!(parse_expression("host(*) == *.example.com").is_err());
+
+        assert!(check("email(2).domain != host(1)").is_ok());
+        assert!(check("email(2).domain != url(3).host").is_err());
+        assert!(check("email(2).user == jane").is_err());
+    }
 }
@@ -1,6 +1,9 @@
 mod cli;
+mod expression;
 mod grammar;
+mod presets;
 mod processor;
+mod record;
 mod tokenizer;
 
 #[macro_use]
@@ -11,8 +14,36 @@ extern crate pest;
 #[macro_use]
 extern crate pest_derive;
 
-fn main() -> Result<(), std::io::Error> {
+use std::process;
+
+/// Exit status when at least one line matched the expression
+const EXIT_MATCH: i32 = 0;
+/// Exit status when no line matched the expression
+const EXIT_NO_MATCH: i32 = 1;
+/// Exit status on usage, expression or I/O errors
+const EXIT_ERROR: i32 = 2;
+
+fn main() {
     env_logger::init();
 
-    processor::process_input(cli::parse_cli())
+    let command_args = match cli::parse_cli() {
+        Ok(command_args) => command_args,
+        // --help and --version are reported as errors by clap but aren't
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            eprintln!("{}", e.message);
+            process::exit(EXIT_ERROR)
+        }
+    };
+
+    let exit_code = match processor::process_input(command_args) {
+        Ok(true) => EXIT_MATCH,
+        Ok(false) => EXIT_NO_MATCH,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_ERROR
+        }
+    };
+
+    process::exit(exit_code)
 }
@@ -0,0 +1,175 @@
+use crate::cli::{DataDef, InputFormat};
+use regex::Regex;
+
+/// The names of the built-in log formats, see [preset].
+pub const PRESET_NAMES: [&str; 7] = [
+    "nginx",
+    "apache-combined",
+    "clf",
+    "syslog-rfc3164",
+    "syslog-rfc5424",
+    "cri",
+    "docker-json",
+];
+
+/// The Common Log Format written by web servers, e.g.
+/// `127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326`.
+/// The request is also split into its method, uri and protocol unless it is malformed.
+const CLF_PATTERN: &str = r#"^(?P<remote_addr>\S+) (?P<ident>\S+) (?P<remote_user>\S+) \[(?P<time_local>[^\]]+)\] "(?P<request>(?:(?P<request_method>[^"\s]+) (?P<request_uri>[^"\s]+)(?: (?P<server_protocol>[^"\s]+))?|[^"]*))" (?P<status>\d{3}) (?P<body_bytes_sent>\S+)"#;
+
+/// The combined log format adds the referer and user agent to the Common Log Format, it is the default
+/// format of both nginx and Apache.
+const COMBINED_PATTERN: &str = r#" "(?P<http_referer>[^"]*)" "(?P<http_user_agent>[^"]*)""#;
+
+/// The web server time format, e.g. 10/Oct/2000:13:55:36 -0700
+const TIME_LOCAL_FORMAT: &str = "%d/%b/%Y:%H:%M:%S %z";
+
+/// BSD syslog, e.g. `<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed`, where the priority is
+/// missing in log files written by syslog daemons.
+const RFC3164_PATTERN: &str = r"^(?:<(?P<pri>\d{1,3})>)?(?P<timestamp>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<hostname>\S+) (?P<app_name>[^:\[\s]+)(?:\[(?P<procid>[^\]]*)\])?: ?(?P<msg>.*)$";
+
+/// The syslog timestamp has no year, the current year is assumed
+const RFC3164_TIMESTAMP_FORMAT: &str = "%b %e %H:%M:%S";
+
+/// Syslog, e.g. `<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3"] An application event`
+const RFC5424_PATTERN: &str = r#"^<(?P<pri>\d{1,3})>(?P<version>\d{1,2}) (?P<timestamp>\S+) (?P<hostname>\S+) (?P<app_name>\S+) (?P<procid>\S+) (?P<msgid>\S+) (?P<structured_data>-|(?:\[(?:[^\]"]|"(?:[^"\\]|\\.)*")*\])+)(?: (?P<msg>.*))?$"#;
+
+/// The container log format of the Kubernetes Container Runtime Interface, e.g.
+/// `2016-10-06T00:17:09.669794202Z stdout F log message`, where the tag is F for a full line and P for a
+/// partial one.
+const CRI_PATTERN: &str = r"^(?P<time>\S+) (?P<stream>stdout|stderr) (?P<logtag>\S+) ?(?P<log>.*)$";
+
+/// Returns how a line of the named log format is split into named fields and the default formats of the
+/// fields that need one, e.g. `timestamp(time_local)` of an nginx access log. The docker json-file format
+/// is read as JSON with the fields log, stream and time.
+pub fn preset(name: &str) -> Result<(InputFormat, Vec<DataDef>), String> {
+    let (pattern, data_defs) = match name {
+        "nginx" | "apache-combined" => (
+            format!("{}{}", CLF_PATTERN, COMBINED_PATTERN),
+            time_local_defs(),
+        ),
+        "clf" => (String::from(CLF_PATTERN), time_local_defs()),
+        "syslog-rfc3164" => (
+            String::from(RFC3164_PATTERN),
+            vec![DataDef::for_field(
+                "timestamp",
+                RFC3164_TIMESTAMP_FORMAT,
+                "timestamp",
+            )],
+        ),
+        "syslog-rfc5424" => (String::from(RFC5424_PATTERN), Vec::new()),
+        "cri" => (String::from(CRI_PATTERN), Vec::new()),
+        "docker-json" => return Ok((InputFormat::Json, Vec::new())),
+        _ => {
+            return Err(format!(
+                "Unknown log format {}, the known formats are: {}",
+                name,
+                PRESET_NAMES.join(", ")
+            ))
+        }
+    };
+
+    let pattern = Regex::new(&pattern).map_err(|e| format!("Invalid pattern {}: {}", name, e))?;
+    Ok((InputFormat::Pattern(pattern), data_defs))
+}
+
+fn time_local_defs() -> Vec<DataDef> {
+    vec![
+        DataDef::for_field("timestamp", TIME_LOCAL_FORMAT, "time_local"),
+        DataDef::for_field("date", TIME_LOCAL_FORMAT, "time_local"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::InputFormat;
+    use crate::presets::{preset, PRESET_NAMES};
+    use crate::record::split_captures;
+
+    fn pattern(name: &str) -> regex::Regex {
+        match preset(name).unwrap() {
+            (InputFormat::Pattern(pattern), _) => pattern,
+            _ => panic!("{} is not a pattern", name),
+        }
+    }
+
+    #[test]
+    fn test_presets() {
+        for name in PRESET_NAMES.iter() {
+            assert!(preset(name).is_ok(), "{}", name);
+        }
+        assert!(preset("iis").is_err());
+    }
+
+    #[test]
+    fn test_nginx() {
+        let pattern = pattern("nginx");
+        let line = r#"10.0.0.1 - - [02/Jan/2026:10:00:00 +0100] "GET /api/users?id=1 HTTP/1.1" 503 0 "-" "curl/8.5.0""#;
+        let record = split_captures(line, &pattern).unwrap();
+
+        assert_eq!(record.field("remote_addr").as_deref(), Some("10.0.0.1"));
+        assert_eq!(
+            record.field("time_local").as_deref(),
+            Some("02/Jan/2026:10:00:00 +0100")
+        );
+        assert_eq!(record.field("request_method").as_deref(), Some("GET"));
+        assert_eq!(
+            record.field("request_uri").as_deref(),
+            Some("/api/users?id=1")
+        );
+        assert_eq!(record.field("status").as_deref(), Some("503"));
+        assert_eq!(
+            record.field("http_user_agent").as_deref(),
+            Some("curl/8.5.0")
+        );
+
+        // a malformed request is kept as is
+        let line = r#"10.0.0.1 - - [02/Jan/2026:10:00:00 +0100] "\x16\x03\x01" 400 157 "-" "-""#;
+        let record = split_captures(line, &pattern).unwrap();
+        assert_eq!(record.field("request").as_deref(), Some(r"\x16\x03\x01"));
+        assert_eq!(record.field("request_method").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_syslog() {
+        let rfc3164 = pattern("syslog-rfc3164");
+        let record = split_captures(
+            "Oct  8 22:14:15 mymachine su[123]: 'su root' failed",
+            &rfc3164,
+        )
+        .unwrap();
+        assert_eq!(
+            record.field("timestamp").as_deref(),
+            Some("Oct  8 22:14:15")
+        );
+        assert_eq!(record.field("app_name").as_deref(), Some("su"));
+        assert_eq!(record.field("procid").as_deref(), Some("123"));
+        assert_eq!(record.field("msg").as_deref(), Some("'su root' failed"));
+
+        let rfc5424 = pattern("syslog-rfc5424");
+        let record = split_captures(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventID="]"] An application event"#,
+            &rfc5424,
+        )
+        .unwrap();
+        assert_eq!(record.field("app_name").as_deref(), Some("evntslog"));
+        assert_eq!(
+            record.field("structured_data").as_deref(),
+            Some(r#"[exampleSDID@32473 iut="3" eventID="]"]"#)
+        );
+        assert_eq!(record.field("msg").as_deref(), Some("An application event"));
+    }
+
+    #[test]
+    fn test_cri() {
+        let cri = pattern("cri");
+        let record = split_captures(
+            "2016-10-06T00:17:09.669794202Z stderr P partial message",
+            &cri,
+        )
+        .unwrap();
+        assert_eq!(record.field("stream").as_deref(), Some("stderr"));
+        assert_eq!(record.field("logtag").as_deref(), Some("P"));
+        assert_eq!(record.field("log").as_deref(), Some("partial message"));
+    }
+}
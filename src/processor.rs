@@ -1,20 +1,103 @@
-use crate::cli::CommandArgs;
-use crate::grammar::{evaluate_line, parse_expression};
-use crate::tokenizer::full_lines;
+use crate::cli::{CommandArgs, DataDef, ErrorPolicy, InputFormat, OutputFormat, Unmatched};
+use crate::grammar::{
+    check_expression, evaluate_record, parse_expression, parse_print_list, referenced_names,
+    select_fields, select_typed_fields, Rule,
+};
+use crate::record::{
+    join_quoted_lines, split_captures, split_csv, split_json, split_logfmt, split_tokens, Record,
+};
+use crate::tokenizer::{full_lines, Line};
 use log::trace;
-use std::io::{self, Write};
-use std::process;
+use pest::iterators::Pairs;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
 
-/// The processor loop which reads lines from stdin, tokenizes the words and runs them through the
+/// The file name reported for lines read from stdin
+const STDIN_NAME: &str = "-";
+
+/// The processor loop which reads lines from the input files or stdin, tokenizes the words and runs them through the
 /// parsed expression and if evaluates to true, writes the same line to stdout. Logging can be configured by
 /// setting the envvariable RUST_LOG to any in [trace, info, warn, error] as described here
 /// [env_logger](https://crates.io/crates/env_logger)
 ///
-pub fn process_input(command_args: CommandArgs) -> Result<(), io::Error> {
+/// Returns `Ok(true)` if at least one line matched the expression, `Ok(false)` if none did and an error if
+/// the expression could not be parsed or evaluated, or if reading/writing failed.
+///
+pub fn process_input(command_args: CommandArgs) -> Result<bool, io::Error> {
+    let stdout = io::stdout();
+    process_files(&command_args, &mut stdout.lock())
+}
+
+/// Filters the lines of the files in order, or of stdin if there are none. Like grep, a file that can't be
+/// opened is reported on stderr and the other files are still filtered, the result is then an error unless
+/// `quiet` is set and a line matched.
+///
+fn process_files(command_args: &CommandArgs, output: &mut impl Write) -> Result<bool, io::Error> {
+    let mut matched = false;
+    let mut skipped = 0;
+    let mut unreadable = 0;
+
+    if command_args.files.is_empty() {
+        let stdin = io::stdin();
+        matched = filter_lines(command_args, STDIN_NAME, stdin.lock(), output, &mut skipped)?;
+    }
+
+    for file in &command_args.files {
+        let input = match File::open(file) {
+            Ok(input) => BufReader::new(input),
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+
+        matched |= filter_lines(command_args, file, input, output, &mut skipped)?;
+
+        if matched && command_args.quiet {
+            break;
+        }
+    }
+
+    // skip drops lines silently
+    if skipped > 0 && command_args.on_error == ErrorPolicy::Warn {
+        eprintln!("{} line(s) skipped due to errors", skipped);
+    }
+
+    if unreadable > 0 && !(matched && command_args.quiet) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} file(s) could not be read", unreadable),
+        ));
+    }
+    Ok(matched)
+}
+
+/// Runs every line of `input` through the expression and writes the matching lines to `output`. If `quiet`
+/// is set nothing is written and the function returns as soon as the first line matches. Lines that fail to
+/// evaluate are handled according to the `on_error` policy, skipped lines are counted in `skipped`.
+///
+fn filter_lines(
+    command_args: &CommandArgs,
+    file: &str,
+    input: impl BufRead,
+    output: &mut impl Write,
+    skipped: &mut usize,
+) -> Result<bool, io::Error> {
     let CommandArgs {
         expr,
         data_def,
         token_regex,
+        quiet,
+        on_error,
+        encoding,
+        print,
+        output: output_format,
+        input: input_format,
+        header: has_header,
+        unmatched,
+        ..
     } = command_args;
 
     trace!(
@@ -23,40 +106,889 @@ pub fn process_input(command_args: CommandArgs) -> Result<(), io::Error> {
         data_def,
         token_regex
     );
-    match parse_expression(expr.as_str()) {
-        Ok(grammar) => {
-            // read lines from stdin, tokenizes the words using regexps and finally writes same line to stdout if it
-            // matches the expression passed in to the program
-            for line in full_lines(io::stdin().lock()) {
-                let line = line?;
-
-                let tokens = token_regex
-                    .split(line.as_str())
-                    .into_iter()
-                    .map(|word| word.trim())
-                    .collect::<Vec<&str>>();
-
-                trace!("main.tokens: {:?}", tokens);
-
-                match evaluate_line(&mut grammar.clone(), &tokens) {
-                    Ok(true) => {
-                        let stdout = io::stdout();
-                        let mut handle = stdout.lock();
-                        handle.write_all(line.as_bytes())?;
-                    }
-                    Err(e) => {
-                        println!("Error in expression: {}", e);
-                        process::exit(-1)
-                    }
-                    Ok(_) => { /* silently ignore this here as its a false positive..*/ }
+
+    let grammar = parse_expression(expr.as_str()).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Error parsing expression: {}", e),
+        )
+    })?;
+    check_expression(&grammar, data_def)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let print_list = match print {
+        Some(print) => Some(parse_print_list(print.as_str()).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Error parsing print list: {}", e),
+            )
+        })?),
+        None => None,
+    };
+
+    let mut matched = false;
+
+    let lines: Box<dyn Iterator<Item = (usize, io::Result<Line>)>> = match input_format {
+        InputFormat::Csv { delimiter } => {
+            Box::new(join_quoted_lines(full_lines(input, encoding), *delimiter))
+        }
+        InputFormat::Split | InputFormat::Pattern(_) | InputFormat::Json | InputFormat::Logfmt => {
+            Box::new(
+                full_lines(input, encoding)
+                    .enumerate()
+                    .map(|(n, l)| (n + 1, l)),
+            )
+        }
+    };
+    let mut header: Option<Vec<String>> = None;
+
+    // the keys of JSON and logfmt lines are only known line by line, a name found in none of the lines is
+    // reported once the input has been read
+    let mut unseen_names = Vec::new();
+    if matches!(input_format, InputFormat::Json | InputFormat::Logfmt) {
+        unseen_names = std::iter::once(&grammar)
+            .chain(print_list.as_ref())
+            .flat_map(referenced_names)
+            .collect();
+        unseen_names.sort_unstable();
+        unseen_names.dedup();
+    } else if !*has_header {
+        check_field_names(&grammar, print_list.as_ref(), &input_format.field_names())?;
+    }
+
+    // read lines from stdin, tokenizes the words using regexps and finally writes same line to stdout if it
+    // matches the expression passed in to the program
+    for (line_number, line) in lines {
+        let line = line?;
+
+        if *has_header && header.is_none() {
+            let names: Vec<String> = split_line(command_args, &line.text, None)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+                .map(|record| record.token_strs().into_iter().map(String::from).collect())
+                .unwrap_or_default();
+            check_field_names(
+                &grammar,
+                print_list.as_ref(),
+                &names.iter().map(String::as_str).collect::<Vec<_>>(),
+            )?;
+            header = Some(names);
+
+            // keep the header row when the matching lines are written as read
+            if !*quiet && *output_format == OutputFormat::Text && print_list.is_none() {
+                output.write_all(&line.raw)?;
+            }
+            continue;
+        }
+
+        let result = split_line(command_args, &line.text, header.as_deref()).and_then(|record| {
+            trace!("main.tokens: {:?}", record);
+
+            let record = match record {
+                Some(record) => record,
+                // a line that doesn't have the input format is never evaluated, but may be passed as read
+                None if *unmatched == Unmatched::Pass => return Ok(Some(line.raw.clone())),
+                None => return Ok(None),
+            };
+            unseen_names.retain(|name| !record.has_field(name));
+
+            if evaluate_record(&mut grammar.clone(), &record, data_def)? {
+                let matching_line = MatchingLine {
+                    file,
+                    line_number,
+                    line: &line,
+                    record: &record,
+                };
+                format_match(command_args, &grammar, print_list.as_ref(), &matching_line).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+
+        match result {
+            Ok(Some(formatted)) => {
+                matched = true;
+
+                if *quiet {
+                    break;
                 }
+                output.write_all(&formatted)?;
             }
+            Err(e) => match on_error {
+                ErrorPolicy::Fail => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("line {}: Error in expression: {}", line_number, e),
+                    ))
+                }
+                ErrorPolicy::Warn => {
+                    eprintln!("line {}: Error in expression: {}", line_number, e);
+                    *skipped += 1;
+                }
+                ErrorPolicy::Skip => *skipped += 1,
+            },
+            Ok(None) => { /* silently ignore this here as its a false positive..*/ }
+        }
+    }
+
+    // only a part of the input has been read when a match was found in quiet mode
+    if !*quiet && !unseen_names.is_empty() {
+        eprintln!(
+            "{}: field name(s) not found in any line: {}",
+            file,
+            unseen_names.join(", ")
+        );
+    }
+
+    Ok(matched)
+}
+
+/// Splits a line into a record according to the input format, the tokens are named by the header if any.
+/// Returns None if the line doesn't have the input format, e.g. it doesn't match the pattern, unless such
+/// lines are split into plain tokens instead.
+fn split_line<'a>(
+    command_args: &'a CommandArgs,
+    text: &'a str,
+    header: Option<&'a [String]>,
+) -> Result<Option<Record<'a>>, String> {
+    let record = match &command_args.input {
+        InputFormat::Split => Some(split_tokens(text, &command_args.token_regex)),
+        InputFormat::Csv { delimiter } => Some(split_csv(text, *delimiter)?),
+        InputFormat::Pattern(pattern) => split_captures(text, pattern),
+        InputFormat::Json => split_json(text),
+        InputFormat::Logfmt => Some(split_logfmt(text)),
+    }
+    .or_else(|| match command_args.unmatched {
+        Unmatched::Split => Some(split_tokens(text, &command_args.token_regex)),
+        Unmatched::Skip | Unmatched::Pass => None,
+    });
+
+    Ok(match header {
+        Some(header) => {
+            record.map(|record| record.with_names(header.iter().map(String::as_str).collect()))
+        }
+        None => record,
+    })
+}
+
+/// Checks that the field names referenced in the expression and print list are known before any line is
+/// processed, so that a misspelled column name is reported once instead of silently matching nothing.
+fn check_field_names(
+    grammar: &Pairs<Rule>,
+    print_list: Option<&Pairs<Rule>>,
+    known_names: &[&str],
+) -> Result<(), io::Error> {
+    let unknown = std::iter::once(grammar)
+        .chain(print_list)
+        .flat_map(referenced_names)
+        .find(|name| !known_names.contains(name));
+
+    match unknown {
+        Some(name) if known_names.is_empty() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown field name {}, field names require a CSV header row (--header), a pattern with named groups (--pattern), JSON (--json) or logfmt (--logfmt) input",
+                name
+            ),
+        )),
+        Some(name) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown field name {}, the known field names are: {}",
+                name,
+                known_names.join(", ")
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// A line that matched the expression and where it was found.
+struct MatchingLine<'a> {
+    file: &'a str,
+    line_number: usize,
+    line: &'a Line,
+    record: &'a Record<'a>,
+}
+
+/// Formats a matching line for output, i.e. the line unchanged, the fields selected by the print list
+/// joined by the output separator or a JSON object.
+fn format_match(
+    command_args: &CommandArgs,
+    grammar: &Pairs<Rule>,
+    print_list: Option<&Pairs<Rule>>,
+    matching_line: &MatchingLine,
+) -> Result<Vec<u8>, String> {
+    match (command_args.output, print_list) {
+        (OutputFormat::Json, _) => {
+            let mut formatted =
+                format_json(grammar, matching_line, &command_args.data_def)?.to_string();
+            formatted.push('\n');
+            Ok(formatted.into_bytes())
+        }
+        (OutputFormat::Text, Some(print_list)) => {
+            let mut formatted =
+                select_fields(print_list, matching_line.record, &command_args.data_def)?
+                    .join(&command_args.output_sep);
+            formatted.push('\n');
+            Ok(formatted.into_bytes())
         }
-        Err(e) => {
-            println!("Error parsing expression: {}", e);
-            process::exit(-1);
+        (OutputFormat::Text, None) => Ok(matching_line.line.raw.clone()),
+    }
+}
+
+/// Creates the JSON object for a matching line holding the line, where it was found, its tokens and the
+/// typed values of the fields referenced in the expression. A field with a wildcard holds an array of all
+/// tokens of the type, otherwise the value or null if there is no such token.
+fn format_json(
+    grammar: &Pairs<Rule>,
+    matching_line: &MatchingLine,
+    data_defs: &[DataDef],
+) -> Result<Value, String> {
+    let mut fields = Map::new();
+
+    for field in select_typed_fields(grammar, matching_line.record, data_defs)? {
+        if fields.contains_key(&field.name) {
+            continue;
         }
+
+        let mut values = field.tokens.iter().map(|(_, token)| token.to_json());
+        let value = if field.wildcard {
+            Value::Array(values.collect())
+        } else {
+            values.next().unwrap_or(Value::Null)
+        };
+
+        fields.insert(field.name, value);
     }
 
-    Ok(())
+    Ok(json!({
+        "line": matching_line.line.text.trim_end_matches(&['\r', '\n'][..]),
+        "file": matching_line.file,
+        "line_number": matching_line.line_number,
+        "tokens": matching_line.record.token_strs(),
+        "fields": fields,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{CommandArgs, ErrorPolicy, InputFormat, OutputFormat, Unmatched};
+    use crate::processor::{filter_lines, process_files, STDIN_NAME};
+    use encoding_rs::{UTF_16LE, UTF_8, WINDOWS_1252};
+    use regex::Regex;
+
+    fn command_args(expr: &str, quiet: bool) -> CommandArgs {
+        CommandArgs {
+            expr: String::from(expr),
+            data_def: Vec::new(),
+            token_regex: Regex::new(" ").unwrap(),
+            quiet,
+            on_error: ErrorPolicy::Warn,
+            encoding: UTF_8,
+            print: None,
+            output_sep: String::from(" "),
+            output: OutputFormat::Text,
+            files: Vec::new(),
+            input: InputFormat::Split,
+            header: false,
+            unmatched: Unmatched::Skip,
+        }
+    }
+
+    const INPUT: &str = "the love 1900-01-01\nnothing here\nthe end 1970-07-31\n";
+
+    #[test]
+    fn test_filter_lines() {
+        let mut output = Vec::new();
+        let matched = filter_lines(
+            &command_args("date(*) > 1800-01-01", false),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "the love 1900-01-01\nthe end 1970-07-31\n"
+        );
+    }
+
+    #[test]
+    fn test_no_match() {
+        let mut output = Vec::new();
+        let matched = filter_lines(
+            &command_args("date(*) == 2000-01-01", false),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(!matched.unwrap());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_quiet() {
+        let mut output = Vec::new();
+        let matched = filter_lines(
+            &command_args("string(*) == the", true),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_expression() {
+        let mut output = Vec::new();
+        assert!(filter_lines(
+            &command_args("xyz(0) == 1", false),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0
+        )
+        .is_err());
+
+        // type errors are reported before any line is read
+        let error = filter_lines(
+            &command_args("date(2) - integer(0) > 5s", false),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid operands date and integer"));
+        assert!(output.is_empty());
+
+        // so are values that aren't of the type, even if no line has a token of the type
+        let error = filter_lines(
+            &command_args("integer(*) == foo", false),
+            STDIN_NAME,
+            INPUT.as_bytes(),
+            &mut output,
+            &mut 0,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("foo"));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_error_policy() {
+        // the second line has a third token which is matched with an invalid regex
+        let input = "ERROR 1900-01-01\nWARN 1900-01-01 x\nERROR 1970-07-31\n";
+        let expr = r"string(0) == ERROR || string(2) match \q";
+
+        let mut output = Vec::new();
+        let mut skipped = 0;
+        let matched = filter_lines(
+            &command_args(expr, false),
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut skipped,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ERROR 1900-01-01\nERROR 1970-07-31\n"
+        );
+
+        // the skipped lines of all files are counted and summarized once
+        filter_lines(
+            &command_args(expr, false),
+            "app.log",
+            input.as_bytes(),
+            &mut Vec::new(),
+            &mut skipped,
+        )
+        .unwrap();
+        assert_eq!(skipped, 2);
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            on_error: ErrorPolicy::Fail,
+            ..command_args(expr, false)
+        };
+        let result = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(result.unwrap_err().to_string().starts_with("line 2:"));
+        assert_eq!(String::from_utf8(output).unwrap(), "ERROR 1900-01-01\n");
+    }
+
+    #[test]
+    fn test_unreadable_file() {
+        let file = std::env::temp_dir().join(format!("logfilter-{}.log", std::process::id()));
+        std::fs::write(&file, INPUT).unwrap();
+        let command_args = CommandArgs {
+            files: vec![
+                String::from("/nonexistent/app.log"),
+                file.to_string_lossy().into_owned(),
+            ],
+            ..command_args("date(*) > 1800-01-01", false)
+        };
+
+        // the other files are filtered but the unreadable file is an error
+        let mut output = Vec::new();
+        let result = process_files(&command_args, &mut output);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("1 file(s) could not be read"));
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+
+        // unless a line matched and only the exit status is of interest
+        let command_args = CommandArgs {
+            quiet: true,
+            ..command_args
+        };
+        assert!(process_files(&command_args, &mut Vec::new()).unwrap());
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let input: &[u8] = b"caf\xe9 ERROR\n\xff\xfe binary\ncaf\xc3\xa9 ERROR\n";
+
+        let mut output = Vec::new();
+        let matched = filter_lines(
+            &command_args("string(1) == ERROR", false),
+            STDIN_NAME,
+            input,
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(output, b"caf\xe9 ERROR\ncaf\xc3\xa9 ERROR\n");
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut output = Vec::new();
+        let command_args_latin1 = CommandArgs {
+            encoding: WINDOWS_1252,
+            ..command_args(r"string(0) match ^caf\x{e9}$", false)
+        };
+        let matched = filter_lines(
+            &command_args_latin1,
+            STDIN_NAME,
+            &b"caf\xe9 ERROR\n"[..],
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(output, b"caf\xe9 ERROR\n");
+
+        // U+0A0A contains a 0x0A byte which must not end the line
+        let input: Vec<u8> = "\u{feff}ERROR \u{a0a}\nWARN x\nERROR y"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+
+        let mut output = Vec::new();
+        let command_args_utf16 = CommandArgs {
+            encoding: UTF_16LE,
+            ..command_args("string(0) == ERROR", false)
+        };
+        let matched = filter_lines(
+            &command_args_utf16,
+            STDIN_NAME,
+            input.as_slice(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(output.len(), input.len() - "WARN x\n".len() * 2);
+    }
+
+    #[test]
+    fn test_print() {
+        let input =
+            "2026-01-01T12:00:00Z GET 10.0.0.1 200\n2026-01-01T12:00:01Z GET 10.0.0.2 500\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            print: Some(String::from("ipv4(*), timestamp(0)")),
+            output_sep: String::from(","),
+            ..command_args("integer(3) == 500", false)
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "10.0.0.2,2026-01-01T12:00:01Z\n"
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        let input = "ERROR 127.0.0.1 1970-07-31 42\nINFO 127.0.0.1 1970-07-31 7\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            output: OutputFormat::Json,
+            ..command_args("integer(3) > 10 && date(*) > 1900-01-01", false)
+        };
+        let matched = filter_lines(
+            &command_args,
+            "app.log",
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "line": "ERROR 127.0.0.1 1970-07-31 42",
+                "file": "app.log",
+                "line_number": 1,
+                "tokens": ["ERROR", "127.0.0.1", "1970-07-31", "42"],
+                "fields": {"integer(3)": 42, "date(*)": ["1970-07-31"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let input = "created_at,status,message\n\
+                     2026-01-02,500,\"upstream failed, retrying\"\n\
+                     2026-01-03,200,ok\n\
+                     2025-12-31,503,\"multi\nline\"\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: InputFormat::Csv { delimiter: b',' },
+            header: true,
+            ..command_args(
+                "date(created_at) > 2026-01-01 && integer(status) > 499",
+                false,
+            )
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "created_at,status,message\n2026-01-02,500,\"upstream failed, retrying\"\n"
+        );
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            print: Some(String::from("string(2)")),
+            ..command_args
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "upstream failed, retrying\n"
+        );
+    }
+
+    #[test]
+    fn test_unknown_field_name() {
+        let input = "created_at,status\n2026-01-02,500\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: InputFormat::Csv { delimiter: b',' },
+            header: true,
+            ..command_args("integer(state) > 499", false)
+        };
+        let result = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unknown field name state"));
+
+        let command_args = CommandArgs {
+            header: false,
+            ..command_args
+        };
+        let result = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pattern() {
+        let input = "2026-01-02 10:00:00 [ERROR] upstream failed\n\
+                     2026-01-02 10:00:01 [INFO] ok\n\
+                     \tat com.example.Main\n\
+                     2026-01-02 10:00:02 [WARN] slow query\n";
+        let pattern = Regex::new(r"(?P<ts>\S+ \S+) \[(?P<lvl>\w+)\] (?P<msg>.*)").unwrap();
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: InputFormat::Pattern(pattern),
+            ..command_args(
+                "timestamp(ts) > 2026-01-02 && loglevel(lvl) >= WARN && string(msg) match ^\\w+",
+                false,
+            )
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2026-01-02 10:00:00 [ERROR] upstream failed\n2026-01-02 10:00:02 [WARN] slow query\n"
+        );
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            unmatched: Unmatched::Pass,
+            ..command_args
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 3);
+
+        // lines passed through are written as read, they have no fields to print
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            print: Some(String::from("string(lvl)")),
+            ..command_args
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("ERROR\n{}\nWARN\n", input.lines().nth(2).unwrap())
+        );
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            expr: String::from("string(message) == x"),
+            ..command_args
+        };
+        let result = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+        assert!(result.unwrap_err().to_string().contains("ts, lvl, msg"));
+    }
+
+    #[test]
+    fn test_json_input() {
+        let input = "{\"ts\": \"2026-01-02T10:00:00Z\", \"status\": 503, \"tags\": [\"db\", \"timeout\"]}\n\
+                     {\"ts\": \"2026-01-02T10:00:01Z\", \"status\": 200, \"tags\": []}\n\
+                     2026-01-02 10:00:02 503 upstream timeout\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: InputFormat::Json,
+            ..command_args(
+                "integer(.status) >= 500 || string(.tags[*]) == timeout",
+                false,
+            )
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            input.lines().next().unwrap().to_owned() + "\n"
+        );
+
+        // lines that aren't JSON fall back to plain tokens, where JSON paths address nothing
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            expr: String::from("integer(.status) >= 500 || string(*) == timeout"),
+            unmatched: Unmatched::Split,
+            ..command_args
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            print: Some(String::from("timestamp(.ts), string(.tags[*])")),
+            ..command_args
+        };
+        filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "2026-01-02T10:00:00Z db timeout\n \n"
+        );
+    }
+
+    #[test]
+    fn test_logfmt() {
+        let input = "level=warn msg=\"slow query\" dur=512ms status=200\n\
+                     level=error msg=\"upstream failed\" status=503\n\
+                     level=info msg=ok status=200\n";
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: InputFormat::Logfmt,
+            print: Some(String::from("string(msg)")),
+            ..command_args("loglevel(level) >= WARN && integer(status) == 200", false)
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(String::from_utf8(output).unwrap(), "slow query\n");
+
+        // key=value pairs can be found in any line
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            expr: String::from("integer(kv:status) >= 500"),
+            input: InputFormat::Split,
+            print: None,
+            ..command_args
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "level=error msg=\"upstream failed\" status=503\n"
+        );
+    }
+
+    #[test]
+    fn test_format_preset() {
+        let input = "10.0.0.1 - - [01/Jan/2026:23:59:59 +0000] \"GET / HTTP/1.1\" 503 0 \"-\" \"curl/8.5.0\"\n\
+                     10.0.0.2 - - [02/Jan/2026:10:00:00 +0100] \"GET /api HTTP/1.1\" 503 0 \"-\" \"curl/8.5.0\"\n\
+                     10.0.0.3 - - [02/Jan/2026:10:00:01 +0100] \"GET /api HTTP/1.1\" 200 512 \"-\" \"curl/8.5.0\"\n";
+        let (input_format, data_def) = crate::presets::preset("nginx").unwrap();
+
+        let mut output = Vec::new();
+        let command_args = CommandArgs {
+            input: input_format,
+            data_def,
+            print: Some(String::from("ipv4(remote_addr), timestamp(time_local)")),
+            ..command_args(
+                "timestamp(time_local) >= 2026-01-02 && integer(status) >= 500",
+                false,
+            )
+        };
+        let matched = filter_lines(
+            &command_args,
+            STDIN_NAME,
+            input.as_bytes(),
+            &mut output,
+            &mut 0,
+        );
+
+        assert!(matched.unwrap());
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "10.0.0.2 02/Jan/2026:10:00:00 +0100\n"
+        );
+    }
 }
@@ -0,0 +1,517 @@
+use crate::tokenizer::Line;
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::io;
+
+/// A line of the input split into tokens. Tokens can also be named, e.g. by the header row of a CSV file,
+/// so that an expression can refer to a token by its name instead of its position.
+#[derive(Debug, Default)]
+pub struct Record<'a> {
+    /// the tokens in the order they are found in the line
+    pub tokens: Vec<Cow<'a, str>>,
+    /// the names of the tokens by position, a token without a name has no entry
+    pub names: Vec<&'a str>,
+    /// the parsed line for JSON input, its values are addressed by path
+    pub json: Option<Value>,
+    /// the line the record was split from, searched for `key=value` pairs
+    pub text: &'a str,
+}
+
+/// A step of a path into a JSON value, i.e. `.key`, `[0]`, `[-1]` for the last element or `[*]` for all
+/// elements of an array.
+#[derive(Debug, PartialEq)]
+pub enum PathSegment<'p> {
+    Key(&'p str),
+    Index(isize),
+    Wildcard,
+}
+
+/// Returns the position of an index in a sequence of `len` elements, a negative index counts from the end.
+/// None if the index is out of bounds.
+pub fn resolve_index(index: isize, len: usize) -> Option<usize> {
+    let position = if index < 0 {
+        len.checked_sub(index.unsigned_abs())?
+    } else {
+        index as usize
+    };
+
+    Some(position).filter(|position| *position < len)
+}
+
+impl<'a> Record<'a> {
+    pub fn with_names(mut self, names: Vec<&'a str>) -> Record<'a> {
+        self.names = names;
+        self
+    }
+
+    /// Returns the token with the given name, if any. For JSON input the name is a top level key.
+    pub fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+        match &self.json {
+            Some(_) => self.path(&[PathSegment::Key(name)]).into_iter().next(),
+            None => self
+                .names
+                .iter()
+                .position(|n| *n == name)
+                .and_then(|index| self.tokens.get(index))
+                .map(|token| Cow::Borrowed(token.as_ref())),
+        }
+    }
+
+    /// Returns true if the record has a token with the given name, even if its value is null.
+    pub fn has_field(&self, name: &str) -> bool {
+        match &self.json {
+            Some(Value::Object(object)) => object.contains_key(name),
+            Some(_) => false,
+            None => self.names.contains(&name),
+        }
+    }
+
+    /// Returns the value of the first `key=value` pair with the key found in the line, whatever the input
+    /// format, e.g. `status=500` in `request failed status=500 user="bob smith"`.
+    pub fn kv(&self, key: &str) -> Option<Cow<'a, str>> {
+        logfmt_pairs(self.text)
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the values of a JSON line found at the path, more than one if the path has a wildcard. A
+    /// missing key or index and null values are not found, arrays and objects are returned as JSON.
+    pub fn path(&self, path: &[PathSegment]) -> Vec<Cow<'_, str>> {
+        let mut values: Vec<&Value> = self.json.iter().collect();
+
+        for segment in path {
+            values = values
+                .into_iter()
+                .flat_map(|value| match (segment, value) {
+                    (PathSegment::Key(key), Value::Object(object)) => {
+                        object.get(*key).into_iter().collect()
+                    }
+                    (PathSegment::Index(index), Value::Array(array)) => {
+                        resolve_index(*index, array.len())
+                            .and_then(|index| array.get(index))
+                            .into_iter()
+                            .collect()
+                    }
+                    (PathSegment::Wildcard, Value::Array(array)) => array.iter().collect(),
+                    (PathSegment::Wildcard, Value::Object(object)) => object.values().collect(),
+                    _ => Vec::new(),
+                })
+                .collect();
+        }
+
+        values.into_iter().filter_map(json_token).collect()
+    }
+
+    /// Returns the tokens as string slices.
+    pub fn token_strs(&self) -> Vec<&str> {
+        self.tokens.iter().map(|token| token.as_ref()).collect()
+    }
+}
+
+impl<'a> From<&[&'a str]> for Record<'a> {
+    fn from(tokens: &[&'a str]) -> Self {
+        Record {
+            tokens: tokens.iter().map(|token| Cow::Borrowed(*token)).collect(),
+            ..Record::default()
+        }
+    }
+}
+
+/// Splits a line into tokens separated by the token regex, e.g. whitespace.
+pub fn split_tokens<'a>(line: &'a str, token_regex: &Regex) -> Record<'a> {
+    Record {
+        tokens: token_regex
+            .split(line)
+            .map(|word| Cow::Borrowed(word.trim()))
+            .collect(),
+        text: line,
+        ..Record::default()
+    }
+}
+
+/// Splits a line into the capture groups of the pattern, the tokens are named by the named groups. A group
+/// that didn't participate in the match is an empty token. Returns None if the line doesn't match.
+pub fn split_captures<'a>(line: &'a str, pattern: &'a Regex) -> Option<Record<'a>> {
+    let captures = pattern.captures(line.trim_end_matches(&['\r', '\n'][..]))?;
+
+    Some(Record {
+        tokens: captures
+            .iter()
+            .skip(1)
+            .map(|group| Cow::Borrowed(group.map(|group| group.as_str()).unwrap_or_default()))
+            .collect(),
+        names: pattern
+            .capture_names()
+            .skip(1)
+            .map(|name| name.unwrap_or_default())
+            .collect(),
+        json: None,
+        text: line,
+    })
+}
+
+/// Splits a line into the fields of a CSV record, quoted fields may contain the delimiter, quotes and
+/// line breaks as described in [RFC 4180](https://tools.ietf.org/html/rfc4180).
+pub fn split_csv(line: &str, delimiter: u8) -> Result<Record<'_>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .from_reader(line.as_bytes());
+
+    match reader.records().next() {
+        Some(Ok(record)) => Ok(Record {
+            tokens: record
+                .iter()
+                .map(|field| Cow::Owned(String::from(field)))
+                .collect(),
+            text: line,
+            ..Record::default()
+        }),
+        Some(Err(e)) => Err(format!("Invalid CSV record: {}", e)),
+        None => Ok(Record::default()),
+    }
+}
+
+/// Parses a JSON line, the tokens are the scalar values in the order of the document. Returns None if the
+/// line isn't JSON.
+pub fn split_json(line: &str) -> Option<Record<'_>> {
+    let json: Value = serde_json::from_str(line).ok()?;
+
+    let mut tokens = Vec::new();
+    collect_scalars(&json, &mut tokens);
+
+    Some(Record {
+        tokens,
+        json: Some(json),
+        text: line,
+        ..Record::default()
+    })
+}
+
+fn collect_scalars(value: &Value, tokens: &mut Vec<Cow<'static, str>>) {
+    match value {
+        Value::Array(array) => array
+            .iter()
+            .for_each(|value| collect_scalars(value, tokens)),
+        Value::Object(object) => object
+            .values()
+            .for_each(|value| collect_scalars(value, tokens)),
+        _ => tokens.extend(json_token(value).map(|token| Cow::Owned(token.into_owned()))),
+    }
+}
+
+/// Returns a JSON value as a token, strings without quotes and other values as JSON. Null is no token.
+fn json_token(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::Null => None,
+        Value::String(string) => Some(Cow::Borrowed(string)),
+        _ => Some(Cow::Owned(value.to_string())),
+    }
+}
+
+/// Splits a logfmt line, e.g. `level=warn msg="slow query" dur=512ms`, into values named by their keys.
+pub fn split_logfmt(line: &str) -> Record<'_> {
+    let (names, tokens) = logfmt_pairs(line).into_iter().unzip();
+
+    Record {
+        tokens,
+        names,
+        text: line,
+        ..Record::default()
+    }
+}
+
+/// Returns the `key=value` pairs of a line in logfmt. A value may be quoted to contain whitespace, where `\"`
+/// and `\\` are unescaped, and a key without `=` has an empty value. Words of free text are keys without
+/// values, so the pairs can be found in any line.
+fn logfmt_pairs(line: &str) -> Vec<(&str, Cow<'_, str>)> {
+    let line = line.trim_end_matches(&['\r', '\n'][..]);
+    let bytes = line.as_bytes();
+    let mut pairs = Vec::new();
+    let mut i = 0;
+
+    // only ASCII bytes are compared, which never occur inside a multi-byte UTF-8 character
+    while i < bytes.len() {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let key_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'=' {
+            i += 1;
+        }
+        let key = &line[key_start..i];
+
+        let value = if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            if i < bytes.len() && bytes[i] == b'"' {
+                i += 1;
+                let value_start = i;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                let value = &line[value_start..i.min(bytes.len())];
+                i += 1;
+                unescape(value)
+            } else {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                Cow::Borrowed(&line[value_start..i])
+            }
+        } else {
+            Cow::Borrowed("")
+        };
+
+        if !key.is_empty() {
+            pairs.push((key, value));
+        }
+    }
+
+    pairs
+}
+
+fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Joins lines that end inside a quoted CSV field with the following lines, so that each item holds one
+/// complete CSV record. The items are numbered by the line the record starts on.
+pub fn join_quoted_lines(
+    lines: impl Iterator<Item = io::Result<Line>>,
+    delimiter: u8,
+) -> impl Iterator<Item = (usize, io::Result<Line>)> {
+    let mut lines = lines.enumerate().map(|(n, line)| (n + 1, line));
+
+    std::iter::from_fn(move || {
+        let (line_number, line) = lines.next()?;
+        let mut line = match line {
+            Ok(line) => line,
+            Err(e) => return Some((line_number, Err(e))),
+        };
+
+        while ends_in_quoted_field(&line.text, char::from(delimiter)) {
+            match lines.next() {
+                Some((_, Ok(next))) => {
+                    line.raw.extend(next.raw);
+                    line.text.push_str(&next.text);
+                }
+                Some((next_line_number, Err(e))) => return Some((next_line_number, Err(e))),
+                None => break,
+            }
+        }
+
+        Some((line_number, Ok(line)))
+    })
+}
+
+/// Returns true if a quoted field of the CSV record is still open at the end of the text. As in RFC 4180
+/// a quote only opens a quoted field at the start of the field, elsewhere it is a plain character, and
+/// within a quoted field an escaped quote is written as two quotes.
+fn ends_in_quoted_field(text: &str, delimiter: char) -> bool {
+    let mut quoted = false;
+    let mut field_start = true;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' && chars.next_if_eq(&'"').is_none() {
+                quoted = false;
+            }
+        } else if c == '"' && field_start {
+            quoted = true;
+        }
+        field_start = !quoted && (c == delimiter || c == '\n');
+    }
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::record::{
+        join_quoted_lines, split_captures, split_csv, split_json, split_logfmt, split_tokens,
+        PathSegment, Record,
+    };
+    use crate::tokenizer::full_lines;
+    use encoding_rs::UTF_8;
+    use regex::Regex;
+
+    #[test]
+    fn test_split_tokens() {
+        let record = split_tokens("a, b,c\n", &Regex::new(",").unwrap());
+        assert_eq!(record.token_strs(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_split_csv() {
+        let record = split_csv("1,\"a, \"\"quoted\"\" b\",,last\r\n", b',').unwrap();
+        assert_eq!(
+            record.token_strs(),
+            vec!["1", "a, \"quoted\" b", "", "last"]
+        );
+
+        let record = split_csv("1\t\"a\tb\"\n", b'\t').unwrap();
+        assert_eq!(record.token_strs(), vec!["1", "a\tb"]);
+    }
+
+    #[test]
+    fn test_split_captures() {
+        let pattern = Regex::new(r"^(?P<ts>\S+ \S+) \[(\w+)\]( (?P<msg>.*))?$").unwrap();
+
+        let record = split_captures("2026-01-02 10:00:00 [ERROR] failed\n", &pattern).unwrap();
+        assert_eq!(
+            record.token_strs(),
+            vec!["2026-01-02 10:00:00", "ERROR", " failed", "failed"]
+        );
+        assert_eq!(record.field("ts").as_deref(), Some("2026-01-02 10:00:00"));
+        assert_eq!(record.field("msg").as_deref(), Some("failed"));
+
+        let record = split_captures("2026-01-02 10:00:00 [INFO]", &pattern).unwrap();
+        assert_eq!(record.field("msg").as_deref(), Some(""));
+
+        assert!(split_captures("\tat com.example.Main", &pattern).is_none());
+    }
+
+    #[test]
+    fn test_field() {
+        let tokens = ["500", "2026-01-01"];
+        let record = Record::from(&tokens[..]).with_names(vec!["status", "created_at"]);
+
+        assert_eq!(record.field("created_at").as_deref(), Some("2026-01-01"));
+        assert_eq!(record.field("unknown"), None);
+    }
+
+    #[test]
+    fn test_split_json() {
+        let record = split_json(
+            r#"{"status": 500, "request": {"user": {"id": "u1"}}, "tags": ["a", "b"], "error": null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(record.token_strs(), vec!["500", "u1", "a", "b"]);
+        assert_eq!(record.field("status").as_deref(), Some("500"));
+        assert_eq!(record.field("error"), None);
+        assert!(record.has_field("error"));
+        assert!(!record.has_field("missing"));
+        assert_eq!(
+            record.path(&[
+                PathSegment::Key("request"),
+                PathSegment::Key("user"),
+                PathSegment::Key("id")
+            ]),
+            vec!["u1"]
+        );
+        assert_eq!(
+            record.path(&[PathSegment::Key("tags"), PathSegment::Wildcard]),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            record.path(&[PathSegment::Key("tags"), PathSegment::Index(1)]),
+            vec!["b"]
+        );
+        assert_eq!(
+            record.path(&[PathSegment::Key("tags"), PathSegment::Index(-2)]),
+            vec!["a"]
+        );
+        assert!(record
+            .path(&[PathSegment::Key("tags"), PathSegment::Index(-3)])
+            .is_empty());
+        assert_eq!(
+            record.path(&[PathSegment::Key("tags")]),
+            vec![r#"["a","b"]"#]
+        );
+        assert!(record
+            .path(&[PathSegment::Key("status"), PathSegment::Key("code")])
+            .is_empty());
+
+        assert!(split_json("2026-01-02 10:00:00 [ERROR] not json").is_none());
+
+        // the keys aren't sorted
+        let record = split_json(r#"{"z": "first", "a": "second"}"#).unwrap();
+        assert_eq!(record.token_strs(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_split_logfmt() {
+        let record = split_logfmt(
+            "level=warn msg=\"slow \\\"users\\\" query\" dur=512ms empty= flag path=/a=b\n",
+        );
+
+        assert_eq!(
+            record.names,
+            vec!["level", "msg", "dur", "empty", "flag", "path"]
+        );
+        assert_eq!(
+            record.token_strs(),
+            vec!["warn", "slow \"users\" query", "512ms", "", "", "/a=b"]
+        );
+        assert_eq!(record.field("dur").as_deref(), Some("512ms"));
+
+        let record = split_logfmt("msg=\"unterminated quote");
+        assert_eq!(record.field("msg").as_deref(), Some("unterminated quote"));
+    }
+
+    #[test]
+    fn test_kv() {
+        let line = "2026-01-02 ERROR failed status=500 user=\"bob smith\" status=200";
+        let record = split_tokens(line, &Regex::new(" ").unwrap());
+
+        assert_eq!(record.kv("status").as_deref(), Some("500"));
+        assert_eq!(record.kv("user").as_deref(), Some("bob smith"));
+        assert_eq!(record.kv("ERROR").as_deref(), Some(""));
+        assert_eq!(record.kv("missing"), None);
+    }
+
+    #[test]
+    fn test_join_quoted_lines() {
+        let input = "id,text\n1,\"multi\nline\"\n2,\"one \"\"line\"\"\"\n";
+        let join = |input: &str, delimiter| {
+            join_quoted_lines(full_lines(input.as_bytes(), UTF_8), delimiter)
+                .map(|(n, line)| (n, line.unwrap().text))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            join(input, b','),
+            vec![
+                (1, String::from("id,text\n")),
+                (2, String::from("1,\"multi\nline\"\n")),
+                (4, String::from("2,\"one \"\"line\"\"\"\n")),
+            ]
+        );
+
+        // a quote within a field doesn't open a quoted field
+        assert_eq!(
+            join("a,5 \"inch,1\nb,x,2\n", b','),
+            vec![
+                (1, String::from("a,5 \"inch,1\n")),
+                (2, String::from("b,x,2\n")),
+            ]
+        );
+        assert_eq!(
+            join("a\t\"multi\nline\"\t1\n", b'\t'),
+            vec![(1, String::from("a\t\"multi\nline\"\t1\n"))]
+        );
+    }
+}
@@ -1,10 +1,17 @@
-use chrono::{NaiveDate, Utc};
+use caseless::default_case_fold_str;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
 use lazy_static::lazy_static;
-use regex::Regex;
+use percent_encoding::percent_decode_str;
+use regex::{Regex, RegexBuilder};
 use semver::Version;
+use std::cmp::Ordering;
+use std::fmt;
 use std::io::{self, BufRead};
 use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
+use std::str::FromStr;
+use url::{ParseError, Position, Url};
 
 /// Semfilter will be able to handle these types.
 ///
@@ -19,9 +26,338 @@ pub enum Token {
     Integer(String, u64),
     Email(String, String),
     Date(String, NaiveDate, String),
+    /// timestamps are normalized to UTC, the format is None when the default formats are used
+    Timestamp(String, NaiveDateTime, Option<String>),
     Ipv4(String, Ipv4Addr),
     Ipv6(String, Ipv6Addr),
     SemVersion(String, Version),
+    LogLevel(String, LogLevel),
+    Duration(String, Duration),
+    /// a size in bytes
+    Bytes(String, u64),
+    Url(String, RequestUrl),
+    /// identifiers are normalized to lower case, a uuid with hyphens, a mac with colons and hex without
+    /// separators, see [parse_uuid], [parse_mac] and [parse_hex]
+    Uuid(String, String),
+    Mac(String, String),
+    Hex(String, String),
+    HttpStatus(String, HttpStatus),
+    Host(String, Host),
+}
+
+/// A URL like https://api.example.com/v2/users?id=1 or a bare request path like /v2/users?id=1 as written in
+/// access logs, which is parsed relative to a placeholder base URL and has no scheme, host or port.
+#[derive(Debug, PartialEq, PartialOrd)]
+pub struct RequestUrl {
+    url: Url,
+    is_path: bool,
+}
+
+impl RequestUrl {
+    /// Parses an absolute URL with a host or a path starting with a slash.
+    fn parse(value: &str) -> Result<RequestUrl, String> {
+        let invalid = |e: ParseError| format!("Invalid url {}: {}", value, e);
+
+        if value.starts_with('/') && !value.starts_with("//") {
+            let url = Url::options()
+                .base_url(Some(&PATH_BASE_URL))
+                .parse(value)
+                .map_err(invalid)?;
+            return Ok(RequestUrl { url, is_path: true });
+        }
+
+        let url = Url::parse(value).map_err(invalid)?;
+        if !url.has_host() || !value.contains("://") {
+            return Err(format!(
+                "Invalid url {}, expected e.g. https://example.com/path or /path",
+                value
+            ));
+        }
+        Ok(RequestUrl {
+            url,
+            is_path: false,
+        })
+    }
+
+    /// Returns a component of the URL, where the path, query and fragment are percent-decoded. None if the
+    /// URL doesn't have it, e.g. the host of a bare path or a missing query parameter.
+    fn component(&self, path: &[&str]) -> Option<String> {
+        let decode = |text: &str| percent_decode_str(text).decode_utf8_lossy().into_owned();
+        let absolute = |component: Option<String>| component.filter(|_| !self.is_path);
+
+        match path {
+            ["scheme"] => absolute(Some(String::from(self.url.scheme()))),
+            ["host"] => absolute(self.url.host_str().map(String::from)),
+            ["port"] => absolute(
+                self.url
+                    .port_or_known_default()
+                    .map(|port| port.to_string()),
+            ),
+            ["path"] => Some(decode(self.url.path())),
+            ["query"] => self.url.query().map(decode),
+            ["query", name] => self
+                .url
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned()),
+            ["fragment"] => self.url.fragment().map(decode),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RequestUrl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.is_path {
+            true => write!(f, "{}", &self.url[Position::BeforePath..]),
+            false => write!(f, "{}", self.url),
+        }
+    }
+}
+
+/// A hostname in lower case without a trailing dot, or in a value of an expression a pattern where a * label
+/// is any single label, e.g. *.internal.example.com, which matches names by [Host::matches].
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Host {
+    name: String,
+}
+
+impl Host {
+    /// Parses a hostname as defined by RFC 1123: labels of up to 63 letters, digits and hyphens that don't
+    /// start or end with a hyphen, at most 253 characters.
+    pub fn parse(value: &str) -> Result<Host, String> {
+        Host::parse_labels(value, false)
+    }
+
+    /// Parses a value of an expression, a hostname or a pattern where a label may be *.
+    pub fn parse_pattern(value: &str) -> Result<Host, String> {
+        Host::parse_labels(value, true)
+    }
+
+    fn parse_labels(value: &str, wildcards: bool) -> Result<Host, String> {
+        let name = value
+            .strip_suffix('.')
+            .unwrap_or(value)
+            .to_ascii_lowercase();
+        let is_label = |label: &str| {
+            (wildcards && label == "*")
+                || (!label.is_empty()
+                    && label.len() <= 63
+                    && label
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                    && !label.starts_with('-')
+                    && !label.ends_with('-'))
+        };
+        if name.len() > 253 || !name.split('.').all(is_label) {
+            return Err(format!("Invalid host value {}", value));
+        }
+        Ok(Host { name })
+    }
+
+    /// Returns true if the host is the domain or a name below it, e.g. api.example.com and example.com are
+    /// subdomains of example.com.
+    pub fn is_subdomain_of(&self, domain: &Host) -> bool {
+        self.name == domain.name || self.name.ends_with(&format!(".{}", domain.name))
+    }
+
+    /// Returns true if the host has a * label, which only a value of an expression can have.
+    pub fn is_pattern(&self) -> bool {
+        self.name.split('.').any(|label| label == "*")
+    }
+
+    /// Returns true if the host has the labels of the pattern, where a * label is any single label, e.g.
+    /// db1.internal.example.com matches *.internal.example.com.
+    pub fn matches(&self, pattern: &Host) -> bool {
+        self.name.split('.').count() == pattern.name.split('.').count()
+            && self
+                .name
+                .split('.')
+                .zip(pattern.name.split('.'))
+                .all(|(label, pattern)| label == pattern || pattern == "*")
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// An HTTP status code, e.g. 404, or in a value of an expression a class, e.g. 4xx. A code is compared with
+/// a class by [HttpStatus::compare_class], so that `status(8) == 5xx` matches 503 and `status(8) < 5xx` 404.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpStatus {
+    Code(u16),
+    Class(u16),
+}
+
+/// The names of common status codes that can be used instead of them, e.g. `status(8) == not_found`.
+const HTTP_STATUS_NAMES: [(&str, u16); 33] = [
+    ("continue", 100),
+    ("switching_protocols", 101),
+    ("ok", 200),
+    ("created", 201),
+    ("accepted", 202),
+    ("no_content", 204),
+    ("moved_permanently", 301),
+    ("found", 302),
+    ("see_other", 303),
+    ("not_modified", 304),
+    ("temporary_redirect", 307),
+    ("permanent_redirect", 308),
+    ("bad_request", 400),
+    ("unauthorized", 401),
+    ("payment_required", 402),
+    ("forbidden", 403),
+    ("not_found", 404),
+    ("method_not_allowed", 405),
+    ("not_acceptable", 406),
+    ("request_timeout", 408),
+    ("conflict", 409),
+    ("gone", 410),
+    ("payload_too_large", 413),
+    ("uri_too_long", 414),
+    ("unsupported_media_type", 415),
+    ("unprocessable_entity", 422),
+    ("too_many_requests", 429),
+    ("internal_server_error", 500),
+    ("not_implemented", 501),
+    ("bad_gateway", 502),
+    ("service_unavailable", 503),
+    ("gateway_timeout", 504),
+    ("http_version_not_supported", 505),
+];
+
+/// The names of the status classes, in order from 1xx to 5xx.
+const HTTP_STATUS_CLASSES: [&str; 5] = [
+    "informational",
+    "success",
+    "redirection",
+    "client_error",
+    "server_error",
+];
+
+impl HttpStatus {
+    /// Parses a status code from 100 to 599 as written in the logs.
+    fn parse(value: &str) -> Result<HttpStatus, String> {
+        match value.parse() {
+            Ok(code) if value.len() == 3 && (100..600).contains(&code) => {
+                Ok(HttpStatus::Code(code))
+            }
+            _ => Err(format!("Invalid http_status value {}", value)),
+        }
+    }
+
+    /// Parses a value of an expression, a status code or a class like 5xx or a name like not_found or
+    /// server_error.
+    fn parse_literal(value: &str) -> Result<HttpStatus, String> {
+        if let Ok(status) = HttpStatus::parse(value) {
+            return Ok(status);
+        }
+
+        let invalid = || format!("Invalid http_status value {}", value);
+        let name = value.to_ascii_lowercase();
+        match name.as_bytes() {
+            [class @ b'1'..=b'5', b'x', b'x'] => Ok(HttpStatus::Class(u16::from(class - b'0'))),
+            _ => HTTP_STATUS_NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, code)| HttpStatus::Code(*code))
+                .or_else(|| {
+                    HTTP_STATUS_CLASSES
+                        .iter()
+                        .position(|n| *n == name)
+                        .map(|i| HttpStatus::Class(i as u16 + 1))
+                })
+                .ok_or_else(invalid),
+        }
+    }
+
+    /// Returns the class of a code or the class itself, e.g. 4 for 404 and 4xx.
+    fn class(self) -> u16 {
+        match self {
+            HttpStatus::Code(code) => code / 100,
+            HttpStatus::Class(class) => class,
+        }
+    }
+
+    /// Compares a status with a value of an expression, where a class is equal to each of its codes and
+    /// ordered like them, e.g. 503 is equal to 5xx and 404 is less.
+    pub fn compare_class(self, value: HttpStatus) -> Option<Ordering> {
+        match value {
+            HttpStatus::Class(class) => self.class().partial_cmp(&class),
+            HttpStatus::Code(_) => self.partial_cmp(&value),
+        }
+    }
+}
+
+/// Codes are ordered by their number and classes by theirs, a code and a class are not ordered by the trait
+/// but by [HttpStatus::compare_class].
+impl PartialOrd for HttpStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (HttpStatus::Code(a), HttpStatus::Code(b)) => a.partial_cmp(b),
+            (HttpStatus::Class(a), HttpStatus::Class(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpStatus::Code(code) => write!(f, "{}", code),
+            HttpStatus::Class(class) => write!(f, "{}xx", class),
+        }
+    }
+}
+
+/// Log levels in order of severity, so that e.g. `loglevel(*) >= WARN` matches warnings and errors.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Notice,
+    Warn,
+    Error,
+    Critical,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    /// Parses a level case insensitively, common aliases like WARNING, ERR or FATAL are accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "TRACE" | "FINEST" => Ok(LogLevel::Trace),
+            "DEBUG" | "FINE" => Ok(LogLevel::Debug),
+            "INFO" | "INFORMATION" => Ok(LogLevel::Info),
+            "NOTICE" => Ok(LogLevel::Notice),
+            "WARN" | "WARNING" => Ok(LogLevel::Warn),
+            "ERROR" | "ERR" | "SEVERE" => Ok(LogLevel::Error),
+            "CRITICAL" | "CRIT" | "FATAL" | "ALERT" | "EMERG" | "EMERGENCY" => {
+                Ok(LogLevel::Critical)
+            }
+            _ => Err(format!("Unknown log level {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Notice => "NOTICE",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Critical => "CRITICAL",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 impl Token {
@@ -44,41 +380,48 @@ impl Token {
                 Some(format) => Token::try_parse_date(value, format),
                 None => Token::try_parse_date(value, "%Y-%m-%d"),
             },
+            "timestamp" => Token::try_parse_timestamp(value, format),
 
-            "email" if validate && EMAIL_REGEX.is_match(value) => Ok(Token::Email(
-                String::from(type_term),
-                String::from(value),
-            )),
-            "ivp4" if validate && value.parse::<Ipv4Addr>().is_ok() => Ok(Token::Ipv4(
-                String::from(type_term),
-                value.parse().unwrap(),
-            )),
-            "ivp6" if validate && value.parse::<Ipv6Addr>().is_ok() => Ok(Token::Ipv6(
-                String::from(type_term),
-                value.parse().unwrap(),
-            )),
-            "semver" if validate && SEMVER_REGEX.is_match(value) => Ok(Token::SemVersion(
-                String::from(type_term),
-                Version::parse(value).unwrap(),
-            )),
-            "number" if validate && NUMBER_REGEX.is_match(value) => Ok(Token::Number(
-                String::from(type_term),
-                value.parse().unwrap(),
-            )),
-            "integer" if validate && INTEGER_REGEX.is_match(value) => Ok(Token::Integer(
-                String::from(type_term),
-                value.parse().unwrap(),
-            )),
-            "string" => Ok(Token::String(
-                String::from(type_term),
-                String::from(value),
-            )),
+            "email" => Token::validate(type_term, value, validate, &EMAIL_REGEX)
+                .map(|_| Token::Email(String::from(type_term), String::from(value))),
+            // ivp4 and ivp6 are the names the types had before the grammar named them ipv4 and ipv6
+            "ipv4" | "ivp4" => {
+                Token::try_parse(type_term, value).map(|v| Token::Ipv4(String::from(type_term), v))
+            }
+            "ipv6" | "ivp6" => {
+                Token::try_parse(type_term, value).map(|v| Token::Ipv6(String::from(type_term), v))
+            }
+            "semver" => Token::validate(type_term, value, validate, &SEMVER_REGEX)
+                .and_then(|_| Token::try_parse(type_term, value))
+                .map(|v| Token::SemVersion(String::from(type_term), v)),
+            "number" => Token::validate(type_term, value, validate, &NUMBER_REGEX)
+                .and_then(|_| Token::try_parse(type_term, value))
+                .map(|v| Token::Number(String::from(type_term), v)),
+            "loglevel" => Token::try_parse(type_term, value)
+                .map(|v| Token::LogLevel(String::from(type_term), v)),
+            "integer" => Token::validate(type_term, value, validate, &INTEGER_REGEX)
+                .and_then(|_| Token::try_parse(type_term, value))
+                .map(|v| Token::Integer(String::from(type_term), v)),
+            "string" => Ok(Token::String(String::from(type_term), String::from(value))),
+            "duration" => {
+                parse_duration(value).map(|v| Token::Duration(String::from(type_term), v))
+            }
+            "bytes" => parse_bytes(value).map(|v| Token::Bytes(String::from(type_term), v)),
+            "url" => RequestUrl::parse(value).map(|v| Token::Url(String::from(type_term), v)),
+            "uuid" => parse_uuid(value).map(|v| Token::Uuid(String::from(type_term), v)),
+            "mac" => parse_mac(value).map(|v| Token::Mac(String::from(type_term), v)),
+            "hex" => parse_hex(value).map(|v| Token::Hex(String::from(type_term), v)),
+            "http_status" | "status" => HttpStatus::parse(value)
+                .map(|v| Token::HttpStatus(String::from(Token::type_name(type_term)), v)),
+            "host" => Host::parse(value).map(|v| Token::Host(String::from(type_term), v)),
 
             _ => Err(format!("Type {} not supported", type_term)),
         }
     }
 
     /// Implements a copy factory method for a Token, currently only tyhe date token is using the format  
+    ///
+    /// The value is parsed as a value of the expression, see [Token::literal].
     pub fn copy(&self, value: &str, format: Option<&str>) -> Result<Token, String> {
         // override the default format if needed...
         let format = if format.is_some() {
@@ -86,7 +429,132 @@ impl Token {
         } else {
             self.get_format()
         };
-        Token::new(self.get_type().as_str(), value, format, false)
+
+        Token::literal(self.get_type().as_str(), value, format)
+    }
+
+    /// Parses a value of the expression that tokens of the type are compared with. A value that doesn't have
+    /// the format is parsed with the default formats of the type, so that a value in the expression can be
+    /// written as e.g. 2026-01-01 when the tokens have a log specific format.
+    pub fn literal(type_term: &str, value: &str, format: Option<&str>) -> Result<Token, String> {
+        // a value can also be a class or a name of status codes or a host pattern, unlike the tokens of the logs
+        match type_term {
+            "http_status" | "status" => {
+                return HttpStatus::parse_literal(value)
+                    .map(|v| Token::HttpStatus(String::from(Token::type_name(type_term)), v))
+            }
+            "host" => {
+                return Host::parse_pattern(value).map(|v| Token::Host(String::from(type_term), v))
+            }
+            _ => {}
+        }
+
+        Token::new_no_validation(type_term, value, format).or_else(|e| match format {
+            Some(format) => Token::new_no_validation(type_term, value, None)
+                .map(|token| token.with_format(format))
+                .map_err(|_| e),
+            None => Err(e),
+        })
+    }
+
+    /// Returns the name of a type that has an alias, e.g. http_status for status, so that fields named
+    /// either way are of the same type.
+    pub fn type_name(type_term: &str) -> &str {
+        match type_term {
+            "status" => "http_status",
+            type_term => type_term,
+        }
+    }
+
+    /// Compares the token with a value of the expression, where the class of a status code, e.g. 5xx,
+    /// is equal to each of its codes and a host pattern, e.g. *.example.com, to each name it matches and
+    /// isn't ordered with other names.
+    pub fn compare_literal(&self, value: &Token) -> Option<Ordering> {
+        match (self, value) {
+            (Token::HttpStatus(_, status), Token::HttpStatus(_, value)) => {
+                status.compare_class(*value)
+            }
+            (Token::Host(_, host), Token::Host(_, pattern)) if pattern.is_pattern() => {
+                Some(Ordering::Equal).filter(|_| host.matches(pattern))
+            }
+            _ => self.partial_cmp(value),
+        }
+    }
+
+    /// Returns the token with the format, which is part of the comparison of dates and timestamps.
+    fn with_format(self, format: &str) -> Token {
+        match self {
+            Token::Date(t, v, _) => Token::Date(t, v, String::from(format)),
+            Token::Timestamp(t, v, _) => Token::Timestamp(t, v, Some(String::from(format))),
+            token => token,
+        }
+    }
+
+    /// Returns the type of a component of a token of the type, e.g. integer for the port of a url, or an
+    /// error if the type has no such component.
+    pub fn component_type(type_term: &str, path: &[&str]) -> Result<&'static str, String> {
+        match (type_term, path) {
+            ("url", ["port"]) => Ok("integer"),
+            ("email", ["domain"]) => Ok("host"),
+            ("url", ["scheme"])
+            | ("url", ["host"])
+            | ("url", ["path"])
+            | ("url", ["query"])
+            | ("url", ["query", _])
+            | ("url", ["fragment"]) => Ok("string"),
+            ("url", _) => Err(format!(
+                "Unknown url component .{}, the components are .scheme, .host, .port, .path, .query, .query.<name> and .fragment",
+                path.join(".")
+            )),
+            ("email", _) => Err(format!(
+                "Unknown email component .{}, the component is .domain",
+                path.join(".")
+            )),
+            _ => Err(format!(
+                "Unknown component .{}, {} has no components",
+                path.join("."),
+                type_term
+            )),
+        }
+    }
+
+    /// Returns the value of a component of the token, see [Token::component_type], None if the token doesn't
+    /// have it.
+    pub fn component(&self, path: &[&str]) -> Option<String> {
+        match self {
+            Token::Url(_, url) => url.component(path),
+            Token::Email(_, email) => match path {
+                ["domain"] => email
+                    .rsplit_once('@')
+                    .map(|(_, domain)| String::from(domain)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Compares the values of two tokens of the same type regardless of their formats, e.g. dates read
+    /// from fields with different format specifiers.
+    pub fn compare_value(&self, other: &Token) -> Option<Ordering> {
+        match (self, other) {
+            (Token::Date(_, a, _), Token::Date(_, b, _)) => a.partial_cmp(b),
+            (Token::Timestamp(_, a, _), Token::Timestamp(_, b, _)) => a.partial_cmp(b),
+            _ => self.partial_cmp(other),
+        }
+    }
+
+    /// Checks the value against the default type validation regex, unless validation is turned off.
+    fn validate(type_term: &str, value: &str, validate: bool, regex: &Regex) -> Result<(), String> {
+        if validate && !regex.is_match(value) {
+            return Err(format!("Invalid {} value {}", type_term, value));
+        }
+        Ok(())
+    }
+
+    fn try_parse<T: FromStr>(type_term: &str, value: &str) -> Result<T, String> {
+        value
+            .parse::<T>()
+            .map_err(|_| format!("Problem parsing {} value {}", type_term, value))
     }
 
     fn try_parse_date(value: &str, f: &str) -> Result<Token, String> {
@@ -113,9 +581,59 @@ impl Token {
         }
     }
 
+    /// Parses a timestamp using the format if given, otherwise RFC 3339 and a few common variants of
+    /// ISO 8601 are tried. A timestamp with an offset is converted to UTC, a date is taken as midnight.
+    /// A format without a year, like the syslog `%b %e %H:%M:%S`, is taken as the current year.
+    fn try_parse_timestamp(value: &str, format: Option<&str>) -> Result<Token, String> {
+        let timestamp = if value == "now()" {
+            Some(Utc::now().naive_utc())
+        } else {
+            match format {
+                Some(f) => DateTime::parse_from_str(value, f)
+                    .map(|t| t.naive_utc())
+                    .or_else(|_| NaiveDateTime::parse_from_str(value, f))
+                    .or_else(|_| {
+                        let year = Utc::now().year();
+                        NaiveDateTime::parse_from_str(
+                            &format!("{} {}", year, value),
+                            &format!("%Y {}", f),
+                        )
+                    })
+                    .ok(),
+                None => DateTime::parse_from_rfc3339(value)
+                    .map(|t| t.naive_utc())
+                    .ok()
+                    .or_else(|| {
+                        TIMESTAMP_FORMATS
+                            .iter()
+                            .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+                    })
+                    .or_else(|| {
+                        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                            .ok()
+                            .and_then(|d| d.and_hms_opt(0, 0, 0))
+                    }),
+            }
+        };
+
+        match timestamp {
+            Some(timestamp) => Ok(Token::Timestamp(
+                String::from("timestamp"),
+                timestamp,
+                format.map(String::from),
+            )),
+            None => Err(format!(
+                "Problem parsing timestamp value {} using format '{}'",
+                value,
+                format.unwrap_or("RFC 3339")
+            )),
+        }
+    }
+
     fn get_value_tuple(&self) -> (String, String, Option<&str>) {
-        match &*self {
+        match self {
             Token::Date(t, v, f) => (t.to_string(), v.to_string(), Some(f)),
+            Token::Timestamp(t, v, f) => (t.to_string(), v.to_string(), f.as_deref()),
             Token::String(t, v) => (t.to_string(), v.to_string(), None),
             Token::Integer(t, v) => (t.to_string(), v.to_string(), None),
             Token::Number(t, v) => (t.to_string(), v.to_string(), None),
@@ -123,6 +641,15 @@ impl Token {
             Token::Ipv4(t, v) => (t.to_string(), v.to_string(), None),
             Token::Ipv6(t, v) => (t.to_string(), v.to_string(), None),
             Token::SemVersion(t, v) => (t.to_string(), v.to_string(), None),
+            Token::LogLevel(t, v) => (t.to_string(), v.to_string(), None),
+            Token::Duration(t, v) => (t.to_string(), v.to_string(), None),
+            Token::Bytes(t, v) => (t.to_string(), v.to_string(), None),
+            Token::Url(t, v) => (t.to_string(), v.to_string(), None),
+            Token::Uuid(t, v) | Token::Mac(t, v) | Token::Hex(t, v) => {
+                (t.to_string(), v.to_string(), None)
+            }
+            Token::HttpStatus(t, v) => (t.to_string(), v.to_string(), None),
+            Token::Host(t, v) => (t.to_string(), v.to_string(), None),
         }
     }
 
@@ -141,12 +668,59 @@ impl Token {
         token_type
     }
 
-    pub fn is_match(&self, regex_val: &str) -> bool {
+    /// Converts the typed value to JSON, numbers are kept as numbers and all other types are written as
+    /// strings, dates and timestamps in ISO 8601.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Token::Number(_, v) => serde_json::Value::from(*v),
+            Token::Integer(_, v) | Token::Bytes(_, v) => serde_json::Value::from(*v),
+            Token::HttpStatus(_, HttpStatus::Code(v)) => serde_json::Value::from(*v),
+            Token::Timestamp(_, v, _) => {
+                serde_json::Value::from(v.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string())
+            }
+            token => serde_json::Value::from(token.get_value()),
+        }
+    }
+
+    /// Matches the value with a regular expression, the flags are any of i (case-insensitive),
+    /// m (multi-line), s (dot matches new line) and x (ignore whitespace).
+    pub fn is_match(&self, regex_val: &str, flags: &str) -> Result<bool, String> {
+        let mut builder = RegexBuilder::new(regex_val);
+        for flag in flags.chars() {
+            match flag {
+                'i' => builder.case_insensitive(true),
+                'm' => builder.multi_line(true),
+                's' => builder.dot_matches_new_line(true),
+                'x' => builder.ignore_whitespace(true),
+                _ => return Err(format!("Invalid regular expression flag {}", flag)),
+            };
+        }
+
         let v = self.get_value();
-        return Regex::new(regex_val).unwrap().is_match(v.as_str());
+        builder
+            .build()
+            .map(|regex| regex.is_match(v.as_str()))
+            .map_err(|e| format!("Invalid regular expression {}: {}", regex_val, e))
+    }
+
+    /// Returns a string or email token with its value case folded, so that tokens differing only in
+    /// case compare equal, e.g. Straße and STRASSE.
+    pub fn fold_case(&self) -> Result<Token, String> {
+        match self {
+            Token::String(t, v) => Ok(Token::String(t.clone(), default_case_fold_str(v))),
+            Token::Email(t, v) => Ok(Token::Email(t.clone(), default_case_fold_str(v))),
+            _ => Err(format!(
+                "Invalid token {}:{}, only string and email types can be compared case-insensitively",
+                self.get_type(),
+                self.get_value()
+            )),
+        }
     }
 }
 
+/// The formats tried in order when parsing a timestamp without a format specifier and it isn't RFC 3339
+const TIMESTAMP_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
 // define default type validations...
 lazy_static! {
     static ref DATE_REGEX: Regex = Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
@@ -155,21 +729,266 @@ lazy_static! {
     static ref INTEGER_REGEX: Regex = Regex::new(r"^\d+$").unwrap();
     static ref SEMVER_REGEX: Regex =
         Regex::new(r"^(0|\d*)\.(0|\d*)\.(0|\d*)(\-\w+(\.\w+)*)?(\+\w+(\.\w+)*)?$").unwrap();
+    static ref DURATION_REGEX: Regex = Regex::new(r"^(\d+(\.\d+)?(ns|us|µs|ms|s|m|h|d))+$").unwrap();
+    static ref ISO_DURATION_REGEX: Regex = Regex::new(
+        r"^P(?:(\d+(?:\.\d+)?)W)?(?:(\d+(?:\.\d+)?)D)?(?:T(?:(\d+(?:\.\d+)?)H)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)S)?)?$"
+    )
+    .unwrap();
+    static ref CLOCK_DURATION_REGEX: Regex =
+        Regex::new(r"^(\d+):([0-5]\d):([0-5]\d(?:\.\d+)?)$").unwrap();
+    static ref DURATION_PART_REGEX: Regex =
+        Regex::new(r"(\d+(?:\.\d+)?)(ns|us|µs|ms|s|m|h|d)").unwrap();
+    static ref BYTES_REGEX: Regex = Regex::new(r"^(\d+(?:\.\d+)?) ?([kKMGTP](?:iB|B)?|B)?$").unwrap();
+    static ref PATH_BASE_URL: Url = Url::parse("http://localhost").unwrap();
+}
+
+/// Parses a UUID with or without hyphens, in braces or as a URN, e.g. 123E4567-E89B-12D3-A456-426614174000,
+/// {123e4567-e89b-12d3-a456-426614174000} or 123e4567e89b12d3a456426614174000, and returns it in lower case
+/// with hyphens.
+pub fn parse_uuid(value: &str) -> Result<String, String> {
+    let uuid = value.strip_prefix("urn:uuid:").unwrap_or(value);
+    let uuid = match uuid.strip_prefix('{') {
+        Some(uuid) => uuid.strip_suffix('}'),
+        None => Some(uuid),
+    };
+    let digits = uuid
+        .and_then(|uuid| match uuid.len() {
+            36 if [8, 13, 18, 23].iter().all(|&i| uuid.as_bytes()[i] == b'-') => {
+                Some(uuid.replace('-', ""))
+            }
+            32 => Some(String::from(uuid)),
+            _ => None,
+        })
+        .filter(|digits| digits.len() == 32 && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| format!("Invalid uuid value {}", value))?
+        .to_ascii_lowercase();
+
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &digits[..8],
+        &digits[8..12],
+        &digits[12..16],
+        &digits[16..20],
+        &digits[20..]
+    ))
+}
+
+/// Parses a MAC address written in pairs separated by colons or hyphens, e.g. 00:1A:2B:3C:4D:5E or
+/// 00-1a-2b-3c-4d-5e, in groups of four separated by dots, e.g. 001a.2b3c.4d5e, or without separators, and
+/// returns it in lower case with colons.
+pub fn parse_mac(value: &str) -> Result<String, String> {
+    let digits = hex_groups(value)
+        .filter(|(digits, size)| digits.len() == 12 && [2, 4, 12].contains(size))
+        .map(|(digits, _)| digits)
+        .ok_or_else(|| format!("Invalid mac value {}", value))?;
+
+    Ok((0..12)
+        .step_by(2)
+        .map(|i| &digits[i..i + 2])
+        .collect::<Vec<_>>()
+        .join(":"))
 }
-pub fn full_lines(mut input: impl BufRead) -> impl Iterator<Item = io::Result<String>> {
+
+/// Parses a hex identifier like a trace or span id, e.g. 00f067aa0ba902b7, with an optional 0x prefix and
+/// groups of the same size separated by colons, hyphens or dots, and returns its digits in lower case. So
+/// that numbers and versions like 200 or 1.2.3 aren't identifiers, a value without the prefix needs at
+/// least 8 digits in groups of at least 2.
+pub fn parse_hex(value: &str) -> Result<String, String> {
+    let prefixed = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"));
+    match hex_groups(prefixed.unwrap_or(value)) {
+        Some((digits, _)) if prefixed.is_some() => Ok(digits),
+        Some((digits, size)) if digits.len() >= 8 && size >= 2 => Ok(digits),
+        _ => Err(format!("Invalid hex value {}", value)),
+    }
+}
+
+/// Returns the lower case digits and the group size of hex digits written in groups of the same size
+/// separated by one of :, - or ., e.g. 00:1a:2b, or None if it isn't.
+fn hex_groups(value: &str) -> Option<(String, usize)> {
+    let groups: Vec<&str> = match value.chars().find(|c| matches!(c, ':' | '-' | '.')) {
+        Some(separator) => value.split(separator).collect(),
+        None => vec![value],
+    };
+    let size = groups[0].len();
+    if size == 0
+        || groups
+            .iter()
+            .any(|group| group.len() != size || !group.bytes().all(|b| b.is_ascii_hexdigit()))
+    {
+        return None;
+    }
+    Some((groups.concat().to_ascii_lowercase(), size))
+}
+
+/// Parses a size in bytes, e.g. 1024, 10K, 3.5MB, 2GiB or 512 kB. The SI units kB (or KB), MB, GB, TB and
+/// PB are powers of 1000, the IEC units KiB, MiB, GiB, TiB and PiB powers of 1024 and so are K, M, G, T and P
+/// without a B as written by du, JVMs and nginx.
+pub fn parse_bytes(value: &str) -> Result<u64, String> {
+    let captures = BYTES_REGEX.captures(value).ok_or_else(|| {
+        format!(
+            "Invalid size {}, e.g. 1024, 10K, 3.5MB, 2GiB or 512 kB",
+            value
+        )
+    })?;
+
+    let unit = captures.get(2).map_or("", |unit| unit.as_str());
+    let exponent = match unit.chars().next() {
+        Some('k') | Some('K') => 1,
+        Some('M') => 2,
+        Some('G') => 3,
+        Some('T') => 4,
+        Some('P') => 5,
+        _ => 0,
+    };
+    let base: u64 = if unit.len() == 2 { 1000 } else { 1024 };
+    let too_large = || format!("Size {} is too large", value);
+
+    // whole numbers are computed exactly, as f64 they would lose precision above 2^53
+    if let Ok(number) = captures[1].parse::<u64>() {
+        return base
+            .checked_pow(exponent)
+            .and_then(|factor| number.checked_mul(factor))
+            .ok_or_else(too_large);
+    }
+
+    let bytes =
+        captures[1].parse::<f64>().unwrap_or_default() * (base as f64).powi(exponent as i32);
+    if bytes > u64::MAX as f64 {
+        return Err(too_large());
+    }
+    Ok(bytes.round() as u64)
+}
+
+/// Parses a duration written as numbers with units, e.g. 512ms, 1.5s or 2m30s where the units are d, h, m, s,
+/// ms, us (or µs) and ns, in ISO 8601, e.g. PT5M or P1DT12H, or as hours, minutes and seconds, e.g.
+/// 00:01:02.345.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let number = |number: &str| number.parse::<f64>().unwrap_or_default();
+
+    let nanos: f64 = if DURATION_REGEX.is_match(value) {
+        DURATION_PART_REGEX
+            .captures_iter(value)
+            .map(|part| {
+                let unit = match &part[2] {
+                    "ns" => 1.0,
+                    "us" | "µs" => 1e3,
+                    "ms" => 1e6,
+                    "s" => 1e9,
+                    "m" => 60e9,
+                    "h" => 3600e9,
+                    _ => 86400e9,
+                };
+                number(&part[1]) * unit
+            })
+            .sum()
+    } else if let Some(iso) = ISO_DURATION_REGEX
+        .captures(value)
+        .filter(|iso| iso.iter().skip(1).any(|part| part.is_some()) && !value.ends_with('T'))
+    {
+        [
+            (1, 604800e9),
+            (2, 86400e9),
+            (3, 3600e9),
+            (4, 60e9),
+            (5, 1e9),
+        ]
+        .iter()
+        .filter_map(|(i, unit)| iso.get(*i).map(|part| number(part.as_str()) * unit))
+        .sum()
+    } else if let Some(clock) = CLOCK_DURATION_REGEX.captures(value) {
+        number(&clock[1]) * 3600e9 + number(&clock[2]) * 60e9 + number(&clock[3]) * 1e9
+    } else {
+        return Err(format!(
+            "Invalid duration {}, e.g. 500ms, 1.5s, 2m30s, PT5M or 00:01:02.345",
+            value
+        ));
+    };
+
+    if nanos > i64::MAX as f64 {
+        return Err(format!("Duration {} is too long", value));
+    }
+    Ok(Duration::nanoseconds(nanos.round() as i64))
+}
+
+/// A line read from the input. The raw bytes are kept as read, including the line terminator, so that a
+/// matching line can be written out unchanged, while the text is the lossily decoded line used for tokenizing.
+#[derive(Debug)]
+pub struct Line {
+    pub raw: Vec<u8>,
+    pub text: String,
+}
+
+/// Reads the input line by line as bytes and decodes each line using the given encoding, invalid byte
+/// sequences are replaced with U+FFFD instead of ending the run.
+pub fn full_lines(
+    mut input: impl BufRead,
+    encoding: &'static Encoding,
+) -> impl Iterator<Item = io::Result<Line>> {
     std::iter::from_fn(move || {
-        let mut vec = String::new();
-        match input.read_line(&mut vec) {
+        let mut raw = Vec::new();
+        match read_raw_line(&mut input, encoding, &mut raw) {
             Ok(0) => None,
-            Ok(_) => Some(Ok(vec)),
+            Ok(_) => {
+                let (text, _) = encoding.decode_with_bom_removal(&raw);
+                let text = text.into_owned();
+                Some(Ok(Line { raw, text }))
+            }
             Err(e) => Some(Err(e)),
         }
     })
 }
 
+/// Reads the bytes of one line into `raw`. For UTF-16 a line ends with the two byte code unit of '\n' and a
+/// 0x0A byte that is just one half of another code unit must not end the line.
+// usize::is_multiple_of would require Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn read_raw_line(
+    input: &mut impl BufRead,
+    encoding: &'static Encoding,
+    raw: &mut Vec<u8>,
+) -> io::Result<usize> {
+    loop {
+        if input.read_until(b'\n', raw)? == 0 || raw.last() != Some(&b'\n') {
+            return Ok(raw.len());
+        }
+
+        let newline_at = raw.len() - 1;
+        if encoding == UTF_16LE {
+            // 0x0A must be the low byte of a code unit, i.e. on an even offset, followed by 0x00
+            if newline_at % 2 == 0 {
+                match input.fill_buf()?.first().copied() {
+                    Some(high_byte) => {
+                        raw.push(high_byte);
+                        input.consume(1);
+                        if high_byte == 0x00 {
+                            return Ok(raw.len());
+                        }
+                    }
+                    None => return Ok(raw.len()),
+                }
+            }
+        } else if encoding == UTF_16BE {
+            // 0x0A must be the low byte of a code unit, i.e. on an odd offset, preceded by 0x00
+            if newline_at % 2 == 1 && raw[newline_at - 1] == 0x00 {
+                return Ok(raw.len());
+            }
+        } else {
+            return Ok(raw.len());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::tokenizer::Token;
+    use crate::tokenizer::{
+        full_lines, parse_bytes, parse_duration, parse_hex, parse_mac, parse_uuid, Host,
+        HttpStatus, Token,
+    };
+    use chrono::{Datelike, Duration};
+    use encoding_rs::{UTF_16BE, UTF_8};
+    use std::cmp::Ordering;
     use std::io::Write;
 
     fn init() {
@@ -219,6 +1038,73 @@ mod tests {
             Token::new("semver", "1.0.0", None, true).unwrap(),
             Token::SemVersion(_, _)
         ));
+        assert!(matches!(
+            Token::new("timestamp", "2026-01-01T12:00:00Z", None, true).unwrap(),
+            Token::Timestamp(_, _, _)
+        ));
+    }
+
+    #[test]
+    fn test_parse_loglevel() {
+        let warn = Token::new("loglevel", "warning", None, true).unwrap();
+
+        assert_eq!(warn.get_value(), "WARN");
+        assert!(warn == warn.copy("WARN", None).unwrap());
+        assert!(warn < warn.copy("Error", None).unwrap());
+        assert!(warn > warn.copy("info", None).unwrap());
+        assert!(Token::new("loglevel", "verbose", None, true).is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let utc = Token::new("timestamp", "2026-01-01T12:00:00Z", None, true).unwrap();
+        let offset = Token::new("timestamp", "2026-01-01T14:00:00.000+02:00", None, true).unwrap();
+        let naive = Token::new("timestamp", "2026-01-01T12:00:00", None, true).unwrap();
+
+        assert!(utc == offset);
+        assert!(utc == naive);
+        assert!(utc > utc.copy("2026-01-01", None).unwrap());
+
+        let formatted = Token::new(
+            "timestamp",
+            "10/Oct/2000:13:55:36 -0700",
+            Some("%d/%b/%Y:%H:%M:%S %z"),
+            true,
+        )
+        .unwrap();
+        assert_eq!(formatted.get_value(), "2000-10-10 20:55:36");
+
+        assert!(Token::new("timestamp", "12:00:00", None, true).is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            Token::new("integer", "42", None, true).unwrap().to_json(),
+            serde_json::json!(42)
+        );
+        assert_eq!(
+            Token::new("number", "2.5", None, true).unwrap().to_json(),
+            serde_json::json!(2.5)
+        );
+        assert_eq!(
+            Token::new("date", "1970/07/31", Some("%Y/%m/%d"), true)
+                .unwrap()
+                .to_json(),
+            serde_json::json!("1970-07-31")
+        );
+        assert_eq!(
+            Token::new("timestamp", "2026-01-01T14:00:00+02:00", None, true)
+                .unwrap()
+                .to_json(),
+            serde_json::json!("2026-01-01T12:00:00Z")
+        );
+        assert_eq!(
+            Token::new("ipv4", "127.0.0.1", None, true)
+                .unwrap()
+                .to_json(),
+            serde_json::json!("127.0.0.1")
+        );
     }
 
     #[test]
@@ -235,18 +1121,310 @@ mod tests {
         assert!(Token::new("date", "1970/07/31", Some("%Y-%m-%d"), true).is_err());
     }
 
+    #[test]
+    fn test_copy_with_default_format() {
+        let format = Some("%d/%b/%Y:%H:%M:%S %z");
+        let token = Token::new("timestamp", "02/Jan/2026:10:00:00 +0100", format, true).unwrap();
+
+        assert!(token == token.copy("02/Jan/2026:10:00:00 +0100", None).unwrap());
+        assert!(token == token.copy("2026-01-02T09:00:00Z", None).unwrap());
+        assert!(token > token.copy("2026-01-02", None).unwrap());
+        assert!(token.copy("yesterday", None).is_err());
+
+        let token =
+            Token::new("timestamp", "Oct  8 22:14:15", Some("%b %e %H:%M:%S"), true).unwrap();
+        assert_eq!(
+            token.get_value(),
+            format!("{}-10-08 22:14:15", chrono::Utc::now().year())
+        );
+    }
+
     #[test]
     fn test_is_match() {
         assert!(Token::new("string", "test", None, true)
             .unwrap()
-            .is_match("test"));
+            .is_match("test", "")
+            .unwrap());
         assert!(!Token::new("string", "test", None, true)
             .unwrap()
-            .is_match("blaha"));
+            .is_match("blaha", "")
+            .unwrap());
+        assert!(Token::new("string", "test", None, true)
+            .unwrap()
+            .is_match("(", "")
+            .is_err());
+        assert!(Token::new("string", "TEST", None, true)
+            .unwrap()
+            .is_match("^test$", "i")
+            .unwrap());
+        assert!(Token::new("string", "ab", None, true)
+            .unwrap()
+            .is_match("a b # comment", "x")
+            .unwrap());
+        let token = Token::new("string", "a\nb", None, true).unwrap();
+        assert!(!token.is_match("a.b", "").unwrap());
+        assert!(token.is_match("a.b", "s").unwrap());
+        assert!(token.is_match("^b$", "m").unwrap());
+    }
+
+    #[test]
+    fn test_fold_case() {
+        let token = Token::new("string", "Straße", None, true).unwrap();
+        assert_eq!(
+            token.fold_case().unwrap(),
+            Token::new("string", "STRASSE", None, true)
+                .unwrap()
+                .fold_case()
+                .unwrap()
+        );
+        assert!(Token::new("integer", "1", None, true)
+            .unwrap()
+            .fold_case()
+            .is_err());
+    }
+
+    #[test]
+    fn test_url_components() {
+        let url = Token::new(
+            "url",
+            "https://API.example.com/v2/caf%C3%A9?user_id=42&q=a+b#top",
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(url.component(&["scheme"]).as_deref(), Some("https"));
+        assert_eq!(url.component(&["host"]).as_deref(), Some("api.example.com"));
+        assert_eq!(url.component(&["port"]).as_deref(), Some("443"));
+        assert_eq!(url.component(&["path"]).as_deref(), Some("/v2/café"));
+        assert_eq!(url.component(&["query", "user_id"]).as_deref(), Some("42"));
+        assert_eq!(url.component(&["query", "q"]).as_deref(), Some("a b"));
+        assert_eq!(url.component(&["query", "x"]), None);
+        assert_eq!(url.component(&["fragment"]).as_deref(), Some("top"));
+
+        let path = Token::new("url", "/api/users?id=1", None, true).unwrap();
+        assert_eq!(path.get_value(), "/api/users?id=1");
+        assert_eq!(path.component(&["host"]), None);
+        assert_eq!(path.component(&["path"]).as_deref(), Some("/api/users"));
+        assert_eq!(path.component(&["query", "id"]).as_deref(), Some("1"));
+
+        assert!(Token::new("url", "key:value", None, true).is_err());
+        assert!(Token::new("url", "users", None, true).is_err());
+        assert_eq!(Token::component_type("url", &["port"]), Ok("integer"));
+        assert!(Token::component_type("url", &["user"]).is_err());
+        assert!(Token::component_type("integer", &["host"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_identifiers() {
+        let uuid = "123e4567-e89b-12d3-a456-426614174000";
+        assert_eq!(
+            parse_uuid("123E4567-E89B-12D3-A456-426614174000").unwrap(),
+            uuid
+        );
+        assert_eq!(
+            parse_uuid("{123e4567-e89b-12d3-a456-426614174000}").unwrap(),
+            uuid
+        );
+        assert_eq!(
+            parse_uuid("urn:uuid:123e4567-e89b-12d3-a456-426614174000").unwrap(),
+            uuid
+        );
+        assert_eq!(
+            parse_uuid("123E4567E89B12D3A456426614174000").unwrap(),
+            uuid
+        );
+        assert!(parse_uuid("123e4567-e89b-12d3-a456-42661417400g").is_err());
+        assert!(parse_uuid("123e4567e-89b-12d3-a456-426614174000").is_err());
+        assert!(parse_uuid("{123e4567-e89b-12d3-a456-426614174000").is_err());
+
+        let mac = "00:1a:2b:3c:4d:5e";
+        assert_eq!(parse_mac("00:1A:2B:3C:4D:5E").unwrap(), mac);
+        assert_eq!(parse_mac("00-1a-2b-3c-4d-5e").unwrap(), mac);
+        assert_eq!(parse_mac("001A.2B3C.4D5E").unwrap(), mac);
+        assert_eq!(parse_mac("001a2b3c4d5e").unwrap(), mac);
+        assert!(parse_mac("00:1a:2b:3c:4d").is_err());
+        assert!(parse_mac("00:1a-2b:3c:4d:5e").is_err());
+        assert!(parse_mac("001:a2b:3c4:d5e").is_err());
+
+        assert_eq!(parse_hex("00F067AA0BA902B7").unwrap(), "00f067aa0ba902b7");
+        assert_eq!(parse_hex("0xDEADbeef").unwrap(), "deadbeef");
+        assert_eq!(parse_hex("de:ad:be:ef").unwrap(), "deadbeef");
+        assert!(parse_hex("0x").is_err());
+        assert!(parse_hex("xyz").is_err());
+        assert!(parse_hex("de:adb:eef").is_err());
+        assert_eq!(parse_hex("0x1F").unwrap(), "1f");
+        assert!(parse_hex("200").is_err());
+        assert!(parse_hex("1.2.3").is_err());
+        assert!(parse_hex("1.2.3.4.5.6.7.8").is_err());
+        assert!(parse_hex("deadbee").is_err());
+    }
+
+    #[test]
+    fn test_parse_http_status() {
+        let code = |value: &str| HttpStatus::parse(value).map(|s| s.to_string());
+        let literal = |value: &str| HttpStatus::parse_literal(value).map(|s| s.to_string());
+        assert_eq!(code("503").unwrap(), "503");
+        assert_eq!(literal("503").unwrap(), "503");
+        assert_eq!(literal("5xx").unwrap(), "5xx");
+        assert_eq!(literal("4XX").unwrap(), "4xx");
+        assert_eq!(literal("not_found").unwrap(), "404");
+        assert_eq!(literal("Server_Error").unwrap(), "5xx");
+        assert!(code("5xx").is_err());
+        assert!(code("not_found").is_err());
+        assert!(code("+99").is_err());
+        assert!(literal("99").is_err());
+        assert!(literal("600").is_err());
+        assert!(literal("6xx").is_err());
+
+        // a code is only equal to its class when compared with it explicitly
+        assert_ne!(HttpStatus::Code(503), HttpStatus::Class(5));
+        assert_eq!(
+            HttpStatus::Code(404).partial_cmp(&HttpStatus::Class(5)),
+            None
+        );
+        assert_eq!(
+            HttpStatus::Code(503).compare_class(HttpStatus::Class(5)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            HttpStatus::Code(404).compare_class(HttpStatus::Class(5)),
+            Some(Ordering::Less)
+        );
+        assert!(HttpStatus::Code(404) < HttpStatus::Code(500));
+        assert_ne!(HttpStatus::Code(503), HttpStatus::Code(500));
+    }
+
+    #[test]
+    fn test_parse_host() {
+        let host = |value: &str| Host::parse(value).map(|h| h.to_string());
+        assert_eq!(host("API.Example.com.").unwrap(), "api.example.com");
+        assert_eq!(host("localhost").unwrap(), "localhost");
+        assert_eq!(host("3com.net").unwrap(), "3com.net");
+        assert_eq!(
+            Host::parse_pattern("*.internal.example.com")
+                .unwrap()
+                .to_string(),
+            "*.internal.example.com"
+        );
+        assert!(host("*.internal.example.com").is_err());
+        assert!(host("-api.example.com").is_err());
+        assert!(host("api..example.com").is_err());
+        assert!(host("api_v2.example.com").is_err());
+        assert!(host(&"a".repeat(64)).is_err());
+        assert!(host("").is_err());
+
+        let api = Host::parse("api.example.com").unwrap();
+        let example = Host::parse("example.com").unwrap();
+        assert!(api.is_subdomain_of(&example));
+        assert!(example.is_subdomain_of(&example));
+        assert!(!example.is_subdomain_of(&api));
+        assert!(!Host::parse("badexample.com")
+            .unwrap()
+            .is_subdomain_of(&example));
+
+        // a pattern matches names but doesn't equal them
+        let pattern = Host::parse_pattern("*.example.com").unwrap();
+        assert!(pattern.is_pattern());
+        assert!(api.matches(&pattern));
+        assert!(!example.matches(&pattern));
+        assert!(!pattern.matches(&api));
+        assert_ne!(api, pattern);
+        assert_eq!(
+            Token::new("email", "jane@Gmail.com", None, true)
+                .unwrap()
+                .component(&["domain"])
+                .as_deref(),
+            Some("Gmail.com")
+        );
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        assert_eq!(parse_bytes("1024").unwrap(), 1024);
+        assert_eq!(parse_bytes("10K").unwrap(), 10240);
+        assert_eq!(parse_bytes("3.5MB").unwrap(), 3_500_000);
+        assert_eq!(parse_bytes("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_bytes("512 kB").unwrap(), 512_000);
+        assert_eq!(parse_bytes("1KB").unwrap(), 1000);
+        assert_eq!(parse_bytes("7B").unwrap(), 7);
+        assert_eq!(
+            parse_bytes("18014398509481985").unwrap(),
+            18_014_398_509_481_985
+        );
+        assert_eq!(parse_bytes("16383PiB").unwrap(), 16383 << 50);
+        assert!(parse_bytes("16384PiB").is_err());
+        assert!(parse_bytes("10X").is_err());
+        assert!(parse_bytes("MB").is_err());
+        assert!(parse_bytes("1mb").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("512ms").unwrap(),
+            Duration::milliseconds(512)
+        );
+        assert_eq!(
+            parse_duration("1.5s").unwrap(),
+            Duration::milliseconds(1500)
+        );
+        assert_eq!(parse_duration("2m30s").unwrap(), Duration::seconds(150));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::hours(24));
+        assert_eq!(
+            parse_duration("250us").unwrap(),
+            Duration::microseconds(250)
+        );
+        assert_eq!(parse_duration("PT5M").unwrap(), Duration::minutes(5));
+        assert_eq!(
+            parse_duration("P1DT12H30.5S").unwrap(),
+            Duration::hours(36) + Duration::milliseconds(30500)
+        );
+        assert_eq!(
+            parse_duration("00:01:02.345").unwrap(),
+            Duration::milliseconds(62345)
+        );
+        assert!(parse_duration("P").is_err());
+        assert!(parse_duration("PT").is_err());
+        assert!(parse_duration("P1M").is_err());
+        assert!(parse_duration("00:61:00").is_err());
+        assert!(parse_duration("5").is_err());
+        assert!(parse_duration("5 s").is_err());
+        assert!(parse_duration("5y").is_err());
+    }
+
+    #[test]
+    fn test_parse_malformed_token() {
+        assert!(Token::new("integer", "42abc", None, true).is_err());
+        assert!(Token::new("semver", "01.2.3", None, true).is_err());
+        assert!(Token::new("ipv4", "127.0.0.256", None, true).is_err());
+        assert!(Token::new("email", "nobody", None, true).is_err());
     }
 
     #[test]
     fn test_parse_invalid_token() {
         assert!(Token::new("invalid", "1.0.0", None, true).is_err());
     }
+
+    #[test]
+    fn test_full_lines() {
+        let lines = full_lines(&b"first\n\xc3\x28 second\nthird"[..], UTF_8)
+            .map(|line| line.unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].raw, b"\xc3\x28 second\n");
+        assert_eq!(lines[1].text, "\u{fffd}( second\n");
+        assert_eq!(lines[2].text, "third");
+
+        // U+0A00 is encoded as 0x0A 0x00 in UTF-16BE which must not end the line
+        let input: Vec<u8> = "a\u{a00}b\nc\n"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_be_bytes())
+            .collect();
+        let lines = full_lines(input.as_slice(), UTF_16BE)
+            .map(|line| line.unwrap().text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec!["a\u{a00}b\n", "c\n"]);
+    }
 }